@@ -1,5 +1,5 @@
 use std::{
-    fs::{self, File},
+    fs::File,
     io::{self, Read, Write},
 };
 
@@ -7,11 +7,20 @@ use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use sha1::{Digest, Sha1};
 
-use crate::helper::get_latest_staged_hash;
+use crate::{
+    helper::write_object_file,
+    hooks::run_hook,
+    worktree::{lock_index, read_index, write_index},
+};
 
 // Git add command implementation
 // TODO: Handle adding same staged file
-pub fn add(file_path: &str) -> io::Result<()> {
+pub fn add(file_path: &str, no_verify: bool) -> io::Result<()> {
+    // Let the pre-add hook reject the file before anything is stored
+    if !no_verify {
+        run_hook("pre-add", &[file_path], &[])?;
+    }
+
     // Open the file to add (e.g. git add main.rs)
     let mut file = File::open(file_path)?;
     let mut contents: Vec<u8> = vec![];
@@ -24,10 +33,12 @@ pub fn add(file_path: &str) -> io::Result<()> {
     let hash = hasher.finalize();
     let hash_str = format!("{:x}", hash);
 
-    // Check the file is modified or make changes before adding to staging area
-    let current_latest_hash = get_latest_staged_hash(file_path)?.unwrap();
+    // Hold the index lock while checking the staged hash so no other command stages in between
+    let index_lock = lock_index()?;
+    let mut index = read_index()?;
 
-    if current_latest_hash == hash_str {
+    // Check the file is modified or make changes before adding to staging area
+    if index.get(file_path) == Some(&hash_str) {
         println!("No changes detected to add to staging area");
         return Ok(());
     }
@@ -36,20 +47,18 @@ pub fn add(file_path: &str) -> io::Result<()> {
     let blob_dir_name = &hash_str[0..2];
     let blob_file_name = &hash_str[2..];
     let blob_file_path = format!(".rgit/objects/{}/{}", blob_dir_name, blob_file_name);
-    fs::create_dir_all(format!(".rgit/objects/{}", blob_dir_name))?;
-    let mut blob_file = File::create(&blob_file_path)?;
 
     // Compress the blob using the DEFLATE algorithm
     let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
     encoder.write_all(&contents)?;
     let compressed_blob = encoder.finish()?;
 
-    // Write the content to the blob file
-    blob_file.write_all(&compressed_blob)?;
+    // Write the content to the blob file (the lock creates the blob folder)
+    write_object_file(&blob_file_path, &compressed_blob)?;
 
-    // Append the hash and file path to the index file
-    let mut index_file = File::options().append(true).open(".rgit/index")?;
-    writeln!(index_file, "{} {}", hash_str, file_path)?;
+    // Stage the hash of the file and rewrite the index
+    index.insert(file_path.to_string(), hash_str.clone());
+    write_index(index_lock, &index)?;
 
     println!("File added to the staging: {}", file_path);
     println!("Stored object as: {}", blob_file_path);
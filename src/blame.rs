@@ -0,0 +1,585 @@
+// `blame`: find the commit that last changed every line of a file
+//
+// Lines start as suspects of the starting commit. Every commit hands the lines it did not
+// change down to its parents through the diff, the remaining lines are blamed on it.
+
+use std::{
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+    fs, io,
+};
+
+use crate::{
+    config::get_config,
+    diff::{diff_lines, read_blob_text, split_lines, Edit},
+    head::Head,
+    linelog::LineRangeSpec,
+    mailmap::Mailmap,
+    object::{peel_to_commit, read_tree, Commit},
+    pretty::{format_date, DateMode},
+    revparse::resolve_revision,
+};
+
+// Minimum number of alphanumeric characters of a block of lines to be detected as moved or copied
+const MOVE_COPY_SCORE: usize = 20;
+
+#[derive(Debug, Clone, Default)]
+pub struct BlameOptions {
+    // Revision to blame the file at, defaults to HEAD
+    pub revision: Option<String>,
+    // Only show these line ranges (`-L`)
+    pub ranges: Vec<String>,
+    // Machine readable output, the commit details are shown once per commit
+    pub porcelain: bool,
+    // Machine readable output with the commit details on every line
+    pub line_porcelain: bool,
+    // Ignore whitespace when comparing lines
+    pub ignore_whitespace: bool,
+    // Detect lines moved within the file
+    pub detect_moves: bool,
+    // Detect lines copied from other files (and moved within the file): 1 for files changed
+    // in the same commit, 2 for any file
+    pub detect_copies: u8,
+    // Commits to look through (`--ignore-rev`)
+    pub ignore_revs: Vec<String>,
+    // Files listing commits to look through (`--ignore-revs-file`)
+    pub ignore_revs_files: Vec<String>,
+}
+
+// Origin of a line: the commit, the file path in that commit and the line number there
+#[derive(Debug, Clone)]
+struct Origin {
+    commit: String,
+    path: String,
+    line: usize,
+}
+
+// Line waiting to be handed down: final line number, line number in the suspect's file
+type Suspect = (usize, usize);
+
+// Commit waiting to be processed, newest first
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct QueuedCommit {
+    timestamp: i64,
+    hash: String,
+}
+
+// Objects read while blaming, each commit, tree and blob is read once
+#[derive(Default)]
+struct ObjectCache {
+    // Authors and committers of the cached commits are shown with their canonical identities
+    mailmap: Mailmap,
+    commits: HashMap<String, Commit>,
+    trees: HashMap<String, BTreeMap<String, String>>,
+    blobs: HashMap<String, String>,
+}
+
+impl ObjectCache {
+    fn commit(&mut self, hash: &str) -> io::Result<&Commit> {
+        if !self.commits.contains_key(hash) {
+            let mut commit = Commit::read(hash)?;
+            for identity in [&mut commit.author, &mut commit.committer] {
+                (identity.name, identity.email) =
+                    self.mailmap.lookup(&identity.name, &identity.email);
+            }
+            self.commits.insert(hash.to_string(), commit);
+        }
+        Ok(&self.commits[hash])
+    }
+
+    fn tree(&mut self, commit: &str) -> io::Result<&BTreeMap<String, String>> {
+        if !self.trees.contains_key(commit) {
+            let tree = read_tree(&self.commit(commit)?.tree)?;
+            self.trees.insert(commit.to_string(), tree);
+        }
+        Ok(&self.trees[commit])
+    }
+
+    // Contents of a file at a commit, None when the file does not exist there
+    fn file(&mut self, commit: &str, path: &str) -> io::Result<Option<String>> {
+        let Some(blob) = self.tree(commit)?.get(path).cloned() else {
+            return Ok(None);
+        };
+        if !self.blobs.contains_key(&blob) {
+            self.blobs
+                .insert(blob.clone(), read_blob_text(Some(&blob))?);
+        }
+        Ok(Some(self.blobs[&blob].clone()))
+    }
+}
+
+// Commits left to process with the lines they are suspected of
+struct BlameState {
+    cache: ObjectCache,
+    pending: HashMap<String, Vec<(String, Vec<Suspect>)>>,
+    queue: BinaryHeap<QueuedCommit>,
+}
+
+impl BlameState {
+    // Make the parent (or another commit) the suspect of the lines
+    fn hand_down(&mut self, commit: &str, path: &str, suspects: Vec<Suspect>) -> io::Result<()> {
+        if suspects.is_empty() {
+            return Ok(());
+        }
+        if !self.pending.contains_key(commit) {
+            self.queue.push(QueuedCommit {
+                timestamp: self.cache.commit(commit)?.committer.timestamp,
+                hash: commit.to_string(),
+            });
+        }
+        let entry = self.pending.entry(commit.to_string()).or_default();
+        match entry.iter_mut().find(|(p, _)| p == path) {
+            Some((_, existing)) => existing.extend(suspects),
+            None => entry.push((path.to_string(), suspects)),
+        }
+        Ok(())
+    }
+
+    // Hand the lines found in another file (or elsewhere in the same file) down to the parent
+    fn find_copies(
+        &mut self,
+        (parent, source_path, source_text): (&str, &str, &str),
+        lines: &[String],
+        remaining: Vec<Suspect>,
+        ignore_whitespace: bool,
+    ) -> io::Result<Vec<Suspect>> {
+        let source_lines = normalize_lines(source_text, ignore_whitespace);
+        let mut remaining = remaining;
+        remaining.sort_by_key(|(_, line)| *line);
+
+        // Find the longest run of consecutive lines present in the source, starting at every line
+        let mut passed = vec![];
+        let mut kept = vec![];
+        let mut i = 0;
+        while i < remaining.len() {
+            let mut best: Option<(usize, usize)> = None;
+            for j in 0..source_lines.len() {
+                let length = (0..)
+                    .take_while(|&k| {
+                        i + k < remaining.len()
+                            && j + k < source_lines.len()
+                            && remaining[i + k].1 == remaining[i].1 + k
+                            && lines[remaining[i + k].1] == source_lines[j + k]
+                    })
+                    .count();
+                if length > 0 && best.is_none_or(|(_, l)| length > l) {
+                    best = Some((j, length));
+                }
+            }
+
+            // The block needs enough content to not match by accident
+            let block = best.filter(|&(_, length)| {
+                let score: usize = remaining[i..i + length]
+                    .iter()
+                    .map(|(_, line)| alphanumeric_count(&lines[*line]))
+                    .sum();
+                score >= MOVE_COPY_SCORE
+            });
+            match block {
+                Some((j, length)) => {
+                    for k in 0..length {
+                        passed.push((remaining[i + k].0, j + k));
+                    }
+                    i += length;
+                }
+                None => {
+                    kept.push(remaining[i]);
+                    i += 1;
+                }
+            }
+        }
+
+        self.hand_down(parent, source_path, passed)?;
+        Ok(kept)
+    }
+}
+
+// Line used for comparisons, without whitespace with -w
+fn normalize(line: &str, ignore_whitespace: bool) -> String {
+    if ignore_whitespace {
+        line.split_whitespace().collect()
+    } else {
+        line.trim_end_matches('\n').to_string()
+    }
+}
+
+fn normalize_lines(text: &str, ignore_whitespace: bool) -> Vec<String> {
+    split_lines(text)
+        .into_iter()
+        .map(|l| normalize(l, ignore_whitespace))
+        .collect()
+}
+
+fn alphanumeric_count(line: &str) -> usize {
+    line.chars().filter(|c| c.is_alphanumeric()).count()
+}
+
+// For every new line, the old line it is unchanged from
+fn unchanged_lines(edits: &[Edit], new_len: usize) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; new_len];
+    for edit in edits {
+        if let Edit::Equal(x, y) = *edit {
+            mapping[y] = Some(x);
+        }
+    }
+    mapping
+}
+
+// For every new line changed by a hunk, the most similar old line of the hunk: the same
+// line apart from whitespace, or the line at the same position when the hunk rewrites lines
+// one by one
+// Used to look through ignored commits, like reformatting commits
+fn similar_lines(old: &[String], new: &[String], edits: &[Edit]) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; new.len()];
+    let mut deleted = vec![];
+    let mut inserted = vec![];
+
+    for edit in edits.iter().chain(std::iter::once(&Edit::Equal(0, 0))) {
+        match *edit {
+            Edit::Delete(x) => deleted.push(x),
+            Edit::Insert(y) => inserted.push(y),
+            Edit::Equal(..) => {
+                if !deleted.is_empty() {
+                    for (k, &y) in inserted.iter().enumerate() {
+                        let same = deleted
+                            .iter()
+                            .find(|&&x| normalize(&old[x], true) == normalize(&new[y], true));
+                        let same_position = (deleted.len() == inserted.len()).then(|| &deleted[k]);
+                        mapping[y] = same.or(same_position).copied();
+                    }
+                }
+                deleted.clear();
+                inserted.clear();
+            }
+        }
+    }
+    mapping
+}
+
+fn read_ignore_revs(options: &BlameOptions) -> io::Result<HashSet<String>> {
+    let mut files = options.ignore_revs_files.clone();
+    if files.is_empty() {
+        files.extend(get_config("[blame]", "ignoreRevsFile")?);
+    }
+
+    let mut revisions = options.ignore_revs.clone();
+    for file in files {
+        let contents = fs::read_to_string(&file).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Cannot read ignore revs file '{}': {}", file, e),
+            )
+        })?;
+        revisions.extend(
+            contents
+                .lines()
+                .map(|l| l.split('#').next().unwrap_or("").trim())
+                .filter(|l| !l.is_empty())
+                .map(String::from),
+        );
+    }
+
+    revisions
+        .iter()
+        .map(|r| peel_to_commit(&resolve_revision(r)?))
+        .collect()
+}
+
+pub fn blame(path: &str, options: &BlameOptions) -> io::Result<()> {
+    let start = match &options.revision {
+        Some(revision) => resolve_revision(revision)?,
+        None => Head::read()?.commit()?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "HEAD does not point to a commit yet",
+            )
+        })?,
+    };
+    let start = peel_to_commit(&start)?;
+    let ignored = read_ignore_revs(options)?;
+
+    let mut cache = ObjectCache {
+        mailmap: Mailmap::load()?,
+        ..ObjectCache::default()
+    };
+    let text = cache.file(&start, path)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("There is no path {} in the commit", path),
+        )
+    })?;
+    let final_lines = split_lines(&text);
+
+    let mut origins: Vec<Option<Origin>> = vec![None; final_lines.len()];
+    let mut state = BlameState {
+        cache,
+        pending: HashMap::new(),
+        queue: BinaryHeap::new(),
+    };
+    let all_lines: Vec<Suspect> = (0..final_lines.len()).map(|i| (i, i)).collect();
+    state.hand_down(&start, path, all_lines)?;
+
+    while let Some(QueuedCommit { hash, .. }) = state.queue.pop() {
+        let Some(suspects_by_path) = state.pending.remove(&hash) else {
+            continue;
+        };
+        let parents = state.cache.commit(&hash)?.parents.clone();
+
+        for (path, suspects) in suspects_by_path {
+            let text = state.cache.file(&hash, &path)?.unwrap_or_default();
+            let lines = normalize_lines(&text, options.ignore_whitespace);
+            let mut remaining = suspects;
+
+            // Unchanged lines come from the first parent that has them
+            for parent in &parents {
+                if remaining.is_empty() {
+                    break;
+                }
+                let Some((parent_path, parent_text)) =
+                    parent_file(&mut state.cache, &hash, parent, &path, &lines, options)?
+                else {
+                    continue;
+                };
+                let parent_lines = normalize_lines(&parent_text, options.ignore_whitespace);
+                let edits = diff_lines(&parent_lines, &lines);
+                let mapping = unchanged_lines(&edits, lines.len());
+
+                let (passed, kept): (Vec<Suspect>, Vec<Suspect>) = remaining
+                    .into_iter()
+                    .partition(|(_, line)| mapping[*line].is_some());
+                let passed = passed
+                    .into_iter()
+                    .map(|(final_line, line)| (final_line, mapping[line].unwrap()))
+                    .collect();
+                state.hand_down(parent, &parent_path, passed)?;
+                remaining = kept;
+            }
+
+            if let Some(parent) = parents.first() {
+                // Lines moved within the file
+                if (options.detect_moves || options.detect_copies > 0) && !remaining.is_empty() {
+                    if let Some(parent_text) = state.cache.file(parent, &path)? {
+                        remaining = state.find_copies(
+                            (parent, &path, &parent_text),
+                            &lines,
+                            remaining,
+                            options.ignore_whitespace,
+                        )?;
+                    }
+                }
+
+                // Lines copied from other files of the parent
+                if options.detect_copies > 0 && !remaining.is_empty() {
+                    let own_tree = state.cache.tree(&hash)?.clone();
+                    let parent_tree = state.cache.tree(parent)?.clone();
+                    for (other_path, blob) in &parent_tree {
+                        let changed = own_tree.get(other_path) != Some(blob);
+                        if other_path == &path || (options.detect_copies < 2 && !changed) {
+                            continue;
+                        }
+                        let other_text = state.cache.file(parent, other_path)?.unwrap_or_default();
+                        remaining = state.find_copies(
+                            (parent, other_path, &other_text),
+                            &lines,
+                            remaining,
+                            options.ignore_whitespace,
+                        )?;
+                        if remaining.is_empty() {
+                            break;
+                        }
+                    }
+                }
+
+                // Changed lines of ignored commits go to the similar line of the parent
+                if ignored.contains(&hash) && !remaining.is_empty() {
+                    if let Some(parent_text) = state.cache.file(parent, &path)? {
+                        let parent_lines = normalize_lines(&parent_text, options.ignore_whitespace);
+                        let edits = diff_lines(&parent_lines, &lines);
+                        let mapping = similar_lines(&parent_lines, &lines, &edits);
+                        let (passed, kept): (Vec<Suspect>, Vec<Suspect>) = remaining
+                            .into_iter()
+                            .partition(|(_, line)| mapping[*line].is_some());
+                        let passed = passed
+                            .into_iter()
+                            .map(|(final_line, line)| (final_line, mapping[line].unwrap()))
+                            .collect();
+                        state.hand_down(parent, &path, passed)?;
+                        remaining = kept;
+                    }
+                }
+            }
+
+            for (final_line, line) in remaining {
+                origins[final_line] = Some(Origin {
+                    commit: hash.clone(),
+                    path: path.clone(),
+                    line,
+                });
+            }
+        }
+    }
+
+    // Lines selected with -L, all lines by default
+    let mut shown = vec![false; final_lines.len()];
+    for range in &options.ranges {
+        let spec = LineRangeSpec::parse(&format!("{}:{}", range, path))?;
+        let (start, end) = spec.resolve(&final_lines)?;
+        shown[start..end].iter_mut().for_each(|s| *s = true);
+    }
+    if options.ranges.is_empty() {
+        shown.iter_mut().for_each(|s| *s = true);
+    }
+
+    let origins: Vec<Origin> = origins
+        .into_iter()
+        .map(|o| o.expect("every line is blamed"))
+        .collect();
+    if options.porcelain || options.line_porcelain {
+        print_porcelain(
+            &mut state.cache,
+            &final_lines,
+            &origins,
+            &shown,
+            options.line_porcelain,
+        )
+    } else {
+        print_blame(&mut state.cache, &final_lines, &origins, &shown, path)
+    }
+}
+
+// File of the parent the lines are compared with, following whole-file renames
+fn parent_file(
+    cache: &mut ObjectCache,
+    commit: &str,
+    parent: &str,
+    path: &str,
+    lines: &[String],
+    options: &BlameOptions,
+) -> io::Result<Option<(String, String)>> {
+    if let Some(text) = cache.file(parent, path)? {
+        return Ok(Some((path.to_string(), text)));
+    }
+
+    // A file removed by the commit that shares at least half of the lines was renamed
+    let own_tree = cache.tree(commit)?.clone();
+    let parent_tree = cache.tree(parent)?.clone();
+    let mut best: Option<(usize, String, String)> = None;
+    for other_path in parent_tree.keys().filter(|p| !own_tree.contains_key(*p)) {
+        let text = cache.file(parent, other_path)?.unwrap_or_default();
+        let other_lines = normalize_lines(&text, options.ignore_whitespace);
+        let common = diff_lines(&other_lines, lines)
+            .iter()
+            .filter(|e| matches!(e, Edit::Equal(..)))
+            .count();
+        if common * 2 >= lines.len().max(1) && best.as_ref().is_none_or(|b| common > b.0) {
+            best = Some((common, other_path.clone(), text));
+        }
+    }
+    Ok(best.map(|(_, path, text)| (path, text)))
+}
+
+fn abbrev_hash(cache: &mut ObjectCache, hash: &str) -> io::Result<String> {
+    // Boundary (root) commits are marked with '^'
+    Ok(if cache.commit(hash)?.parents.is_empty() {
+        format!("^{}", &hash[..7])
+    } else {
+        hash[..8].to_string()
+    })
+}
+
+fn print_blame(
+    cache: &mut ObjectCache,
+    lines: &[&str],
+    origins: &[Origin],
+    shown: &[bool],
+    path: &str,
+) -> io::Result<()> {
+    // File names are shown when some lines come from another file
+    let show_paths = origins.iter().any(|o| o.path != path);
+    let path_width = origins.iter().map(|o| o.path.len()).max().unwrap_or(0);
+    let mut author_width = 0;
+    for origin in origins {
+        author_width = author_width.max(cache.commit(&origin.commit)?.author.name.chars().count());
+    }
+    let number_width = lines.len().to_string().len();
+
+    for (i, line) in lines.iter().enumerate() {
+        if !shown[i] {
+            continue;
+        }
+        let origin = &origins[i];
+        let hash = abbrev_hash(cache, &origin.commit)?;
+        let author = cache.commit(&origin.commit)?.author.clone();
+        let path_column = if show_paths {
+            format!(" {:<width$}", origin.path, width = path_width)
+        } else {
+            String::new()
+        };
+        println!(
+            "{}{} ({:<author_width$} {} {:>number_width$}) {}",
+            hash,
+            path_column,
+            author.name,
+            format_date(&author, DateMode::Iso),
+            i + 1,
+            line.trim_end_matches('\n'),
+            author_width = author_width,
+            number_width = number_width,
+        );
+    }
+
+    Ok(())
+}
+
+fn print_porcelain(
+    cache: &mut ObjectCache,
+    lines: &[&str],
+    origins: &[Origin],
+    shown: &[bool],
+    every_line: bool,
+) -> io::Result<()> {
+    let mut described: HashSet<String> = HashSet::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if !shown[i] {
+            continue;
+        }
+        let origin = &origins[i];
+
+        // The first line of a group of consecutive lines from the same commit has the group size
+        let starts_group = i == 0
+            || !shown[i - 1]
+            || origins[i - 1].commit != origin.commit
+            || origins[i - 1].line + 1 != origin.line;
+        if starts_group {
+            let size = (i..lines.len())
+                .take_while(|&j| {
+                    shown[j]
+                        && origins[j].commit == origin.commit
+                        && origins[j].line == origin.line + (j - i)
+                })
+                .count();
+            println!("{} {} {} {}", origin.commit, origin.line + 1, i + 1, size);
+        } else {
+            println!("{} {} {}", origin.commit, origin.line + 1, i + 1);
+        }
+
+        if every_line || described.insert(origin.commit.clone()) {
+            let commit = cache.commit(&origin.commit)?.clone();
+            for (role, identity) in [("author", &commit.author), ("committer", &commit.committer)] {
+                println!("{} {}", role, identity.name);
+                println!("{}-mail <{}>", role, identity.email);
+                println!("{}-time {}", role, identity.timestamp);
+                println!("{}-tz {}", role, identity.timezone);
+            }
+            println!("summary {}", commit.subject());
+            match commit.parents.first() {
+                Some(parent) => println!("previous {} {}", parent, origin.path),
+                None => println!("boundary"),
+            }
+            println!("filename {}", origin.path);
+        }
+        println!("\t{}", line.trim_end_matches('\n'));
+    }
+
+    Ok(())
+}
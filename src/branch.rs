@@ -1,62 +1,440 @@
-use std::{
-    fs::{self},
-    io::{self},
-    path::Path,
+use std::{collections::HashSet, io};
+
+use crate::{
+    config::{copy_config_section, get_config, remove_config_section, rename_config_section},
+    head::Head,
+    object::{peel_to_commit, Commit},
+    reflog::{delete_reflog, move_reflog, read_reflog},
+    refs::{delete_ref, list_refs, read_ref, write_ref, RefTransaction},
+    revparse::{check_ref_format, glob_regex, resolve_revision, shorten_ref_name},
+    revwalk::ancestors,
 };
 
-// Get list of branch or create new branch
-pub fn branch(new_branch: Option<&String>) -> io::Result<()> {
-    // Check heads dir exist (to store branches)
-    let heads_dir = Path::new(".rgit/refs/heads");
-    if !heads_dir.exists() {
+#[derive(Debug, Clone, Default)]
+pub struct BranchOptions {
+    // Commit the new branch points to, defaults to HEAD
+    pub start_point: Option<String>,
+    // Reset the branch to the start point when it already exists
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BranchListOptions {
+    // 1: show the tip and subject, 2: also the upstream name
+    pub verbose: u8,
+    // Only list branches merged into this commit
+    pub merged: Option<String>,
+    // Only list branches not merged into this commit
+    pub no_merged: Option<String>,
+    // Only list branches containing this commit
+    pub contains: Option<String>,
+    // Sort key: refname, committerdate, authordate or objectname, '-' for descending order
+    // (defaults to `branch.sort`, then refname)
+    pub sort: Option<String>,
+    // Only list branches matching one of these globs
+    pub patterns: Vec<String>,
+}
+
+// Branch shown by `branch` listings
+struct ListedBranch {
+    name: String,
+    tip: String,
+    commit: Commit,
+}
+
+// List branches, the current one marked with '*'
+pub fn list_branches(options: &BranchListOptions) -> io::Result<()> {
+    let heads = list_refs("refs/heads/")?;
+    if heads.is_empty() {
         println!("No branches available");
         return Ok(());
     }
 
-    if let Some(new_branch) = new_branch {
-        // Create new branch from current HEAD branch if new_branch have value
-        let master_file = heads_dir.join("master"); // TODO :: Fix heard coded branch name to dynamic
-        let new_branch_file = heads_dir.join(new_branch);
+    let commit_ancestors = |revision: &Option<String>| -> io::Result<Option<HashSet<String>>> {
+        match revision {
+            Some(revision) => Ok(Some(ancestors(&[peel_to_commit(&resolve_revision(
+                revision,
+            )?)?])?)),
+            None => Ok(None),
+        }
+    };
+    let merged = commit_ancestors(&options.merged)?;
+    let no_merged = commit_ancestors(&options.no_merged)?;
+    let contains = match &options.contains {
+        Some(revision) => Some(peel_to_commit(&resolve_revision(revision)?)?),
+        None => None,
+    };
+    let patterns = options
+        .patterns
+        .iter()
+        .map(|pattern| glob_regex(pattern))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Branches like "feature/x" live in sub directories
+    let mut branches = vec![];
+    for (refname, tip) in heads {
+        let name = refname["refs/heads/".len()..].to_string();
+        if !patterns.is_empty() && !patterns.iter().any(|pattern| pattern.is_match(&name)) {
+            continue;
+        }
+        if merged.as_ref().is_some_and(|merged| !merged.contains(&tip))
+            || no_merged
+                .as_ref()
+                .is_some_and(|merged| merged.contains(&tip))
+        {
+            continue;
+        }
+        if let Some(commit) = &contains {
+            if !ancestors(std::slice::from_ref(&tip))?.contains(commit) {
+                continue;
+            }
+        }
+        let commit = Commit::read(&tip)?;
+        branches.push(ListedBranch { name, tip, commit });
+    }
+
+    let sort = match &options.sort {
+        Some(sort) => Some(sort.clone()),
+        None => get_config("[branch]", "sort")?,
+    };
+    if let Some(sort) = sort {
+        let (descending, key) = match sort.strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, sort.as_str()),
+        };
+        match key {
+            "refname" => {}
+            "committerdate" => branches.sort_by_key(|b| b.commit.committer.timestamp),
+            "authordate" => branches.sort_by_key(|b| b.commit.author.timestamp),
+            "objectname" => branches.sort_by(|a, b| a.tip.cmp(&b.tip)),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unsupported sort key '{}'", key),
+                ))
+            }
+        }
+        if descending {
+            branches.reverse();
+        }
+    }
+
+    let head = Head::read()?;
+    let current = head.branch();
+    let width = branches
+        .iter()
+        .map(|b| b.name.chars().count())
+        .max()
+        .unwrap_or(0);
 
-        if new_branch_file.exists() {
-            println!("Branch {new_branch} already exist");
-            return Ok(());
+    println!("Branches:");
+    // A detached HEAD is listed first
+    if let (Head::Detached(commit), true) = (&head, options.patterns.is_empty()) {
+        println!("* (HEAD detached at {})", &commit[..7]);
+    }
+    for branch in &branches {
+        let marker = if current == Some(branch.name.as_str()) {
+            '*'
         } else {
-            fs::copy(master_file, new_branch_file)?;
-            println!("Branch {} created.", new_branch);
+            ' '
+        };
+        if options.verbose == 0 {
+            println!("{} {}", marker, branch.name);
+            continue;
         }
+        println!(
+            "{} {:<width$} {} {}{}",
+            marker,
+            branch.name,
+            &branch.tip[..7],
+            upstream_status(&branch.name, &branch.tip, options.verbose > 1)?,
+            branch.commit.subject(),
+            width = width,
+        );
+    }
+
+    Ok(())
+}
+
+// "[origin/main: ahead 1, behind 2] " for verbose listings, the upstream name only with `show_name`
+fn upstream_status(name: &str, tip: &str, show_name: bool) -> io::Result<String> {
+    let Some(upstream) = branch_upstream(name)? else {
+        return Ok(String::new());
+    };
+    let upstream_name = shorten_ref_name(&upstream);
+    let Some(upstream_tip) = read_ref(&upstream)? else {
+        return Ok(if show_name {
+            format!("[{}: gone] ", upstream_name)
+        } else {
+            String::new()
+        });
+    };
+
+    let ours = ancestors(&[tip.to_string()])?;
+    let theirs = ancestors(&[upstream_tip])?;
+    let ahead = ours.difference(&theirs).count();
+    let behind = theirs.difference(&ours).count();
+    let mut counts = vec![];
+    if ahead > 0 {
+        counts.push(format!("ahead {}", ahead));
+    }
+    if behind > 0 {
+        counts.push(format!("behind {}", behind));
+    }
+
+    Ok(match (show_name, counts.is_empty()) {
+        (true, true) => format!("[{}] ", upstream_name),
+        (true, false) => format!("[{}: {}] ", upstream_name, counts.join(", ")),
+        (false, true) => String::new(),
+        (false, false) => format!("[{}] ", counts.join(", ")),
+    })
+}
+
+// Rename (or copy) a branch, the current branch when `old` is not given, with its configuration
+// `force` replaces an existing branch with the new name
+pub fn rename_branch(old: Option<&str>, new: &str, copy: bool, force: bool) -> io::Result<()> {
+    let head = Head::read()?;
+    let current = head.refname();
+    let old = match old {
+        Some(old) => old.to_string(),
+        None => head.branch().map(|name| name.to_string()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "HEAD is detached, give the branch to rename",
+            )
+        })?,
+    };
+    check_branch_name(new)?;
+
+    let old_ref = format!("refs/heads/{}", old);
+    let new_ref = format!("refs/heads/{}", new);
+    let Some(tip) = read_ref(&old_ref)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Branch '{}' not found", old),
+        ));
+    };
+    if read_ref(&new_ref)?.is_some() && !force && old != new {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "A branch named '{}' already exists (use -M or -C to replace it)",
+                new
+            ),
+        ));
+    }
+    if old != new && force && current == Some(new_ref.as_str()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot force update the current branch '{}'", new),
+        ));
+    }
+
+    // The old branch goes away and the new one appears in a single step, taking its log along
+    let message = format!(
+        "Branch: {} {} to {}",
+        if copy { "copied" } else { "renamed" },
+        old_ref,
+        new_ref
+    );
+    if old != new {
+        move_reflog(&old_ref, &new_ref, copy)?;
+        let mut transaction = RefTransaction::new(&message);
+        if !copy {
+            transaction.delete(&old_ref, Some(&tip), false);
+        }
+        transaction.update(&new_ref, &tip, None, false);
+        if let Err(e) = transaction.commit() {
+            if copy {
+                delete_reflog(&new_ref)?;
+            } else {
+                move_reflog(&new_ref, &old_ref, false)?;
+            }
+            return Err(e);
+        }
+    }
+    if copy {
+        copy_config_section(&branch_section(&old), &branch_section(new))?;
     } else {
-        // Show branches that have in heads dir
-        println!("Branches:");
-        for entry in fs::read_dir(heads_dir)? {
-            let entry = entry?;
-            let branch_name = entry.file_name().into_string().unwrap();
-            println!("  {branch_name}");
+        rename_config_section(&branch_section(&old), &branch_section(new))?;
+        // HEAD follows the renamed branch
+        if current == Some(old_ref.as_str()) {
+            Head::Symbolic(new_ref.clone()).write(&message)?;
         }
     }
 
+    if copy {
+        println!("Branch {} copied to {}", old, new);
+    } else {
+        println!("Branch {} renamed to {}", old, new);
+    }
     Ok(())
 }
 
-pub fn delete_branch(branch_name: &str) -> io::Result<()> {
-    // Check heads dir exist (to store branches)
-    let heads_dir = Path::new(".rgit/refs/heads");
-    if !heads_dir.exists() {
-        println!("No branches available to delete");
-        return Ok(());
+// Configuration section of a branch, e.g. `[branch "main"]`
+fn branch_section(name: &str) -> String {
+    format!("[branch \"{}\"]", name)
+}
+
+// Branch names follow the reference name rules, "HEAD" and names starting with '-' are not allowed
+pub fn check_branch_name(name: &str) -> io::Result<()> {
+    if name == "HEAD" || name.starts_with('-') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid branch name", name),
+        ));
     }
+    check_ref_format(&format!("refs/heads/{}", name))
+}
 
-    let delete_branch_file = heads_dir.join(branch_name);
+// Create a branch at the start point (HEAD by default), or reset it with `force`
+pub fn branch(name: &str, options: &BranchOptions) -> io::Result<()> {
+    check_branch_name(name)?;
+    let refname = format!("refs/heads/{}", name);
 
-    // TODO :: Do I need check the current active branch is our want to delete branch
-    if !delete_branch_file.exists() {
-        println!("Branch '{branch_name}' not found");
-        return Ok(());
+    let start = match &options.start_point {
+        Some(start_point) => peel_to_commit(&resolve_revision(start_point)?)?,
+        None => Head::read()?.commit()?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Not a valid object name: 'HEAD' (nothing committed yet)",
+            )
+        })?,
+    };
+
+    let exists = read_ref(&refname)?.is_some();
+    if exists && !options.force {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "A branch named '{}' already exists (use -f to reset it)",
+                name
+            ),
+        ));
+    }
+    if exists && Head::read()?.refname() == Some(refname.as_str()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot force update the current branch '{}'", name),
+        ));
+    }
+
+    let start_name = options.start_point.as_deref().unwrap_or("HEAD");
+    let message = if exists {
+        format!("branch: Reset to {}", start_name)
+    } else {
+        format!("branch: Created from {}", start_name)
+    };
+    write_ref(&refname, &start, None, false, &message)?;
+
+    if exists {
+        println!("Branch {} reset to {}", name, &start[..7]);
+    } else {
+        println!("Branch {} created.", name);
+    }
+    Ok(())
+}
+
+// Upstream of a branch from `branch.<name>.remote` and `branch.<name>.merge`,
+// e.g. "refs/remotes/origin/main" (a local branch when the remote is ".")
+pub fn branch_upstream(name: &str) -> io::Result<Option<String>> {
+    let section = branch_section(name);
+    let (Some(remote), Some(merge)) = (
+        get_config(&section, "remote")?,
+        get_config(&section, "merge")?,
+    ) else {
+        return Ok(None);
+    };
+    let branch = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+    Ok(Some(if remote == "." {
+        format!("refs/heads/{}", branch)
     } else {
-        // Delete branch file
-        fs::remove_file(delete_branch_file)?;
-        println!("Branch '{branch_name}' successfully deleted");
+        format!("refs/remotes/{}/{}", remote, branch)
+    }))
+}
+
+// Delete branches, `force` skips the check that their commits are merged
+pub fn delete_branch(branch_names: &[String], force: bool) -> io::Result<()> {
+    let current = Head::read()?;
+    let head = current.commit()?;
+    for branch_name in branch_names {
+        let refname = format!("refs/heads/{}", branch_name);
+        let Some(tip) = read_ref(&refname)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Branch '{}' not found", branch_name),
+            ));
+        };
+        if current.refname() == Some(refname.as_str()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot delete branch '{}' checked out", branch_name),
+            ));
+        }
+
+        // Unless forced, the commits of the branch have to be merged into its upstream,
+        // or into HEAD when it has none
+        if !force {
+            let upstream = match branch_upstream(branch_name)? {
+                Some(upstream) => read_ref(&upstream)?.map(|hash| (upstream, hash)),
+                None => None,
+            };
+            let target = match &upstream {
+                Some((_, hash)) => Some(hash.clone()),
+                None => head.clone(),
+            };
+            if !target.is_some_and(|target| is_ancestor(&tip, &target).unwrap_or(false)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "The branch '{}' is not fully merged.\nIf you are sure you want to delete it, run 'rgit branch -D {}'",
+                        branch_name, branch_name
+                    ),
+                ));
+            }
+            if let Some((upstream, _)) = &upstream {
+                if !head
+                    .as_ref()
+                    .is_some_and(|head| is_ancestor(&tip, head).unwrap_or(false))
+                {
+                    println!(
+                        "warning: deleting branch '{}' that has been merged to '{}', but not yet merged to HEAD",
+                        branch_name,
+                        shorten_ref_name(upstream)
+                    );
+                }
+            }
+        }
+
+        // Delete branch file, and the directories left empty by names like "feature/x"
+        delete_ref(&refname, Some(&tip), false)?;
+        remove_config_section(&branch_section(branch_name))?;
+
+        println!("Deleted branch {} (was {}).", branch_name, &tip[..7]);
+        if in_head_log(&tip)? {
+            println!(
+                "hint: the tip is still recorded in the HEAD log (.rgit/logs/HEAD), restore it with 'rgit branch {} {}'",
+                branch_name, tip
+            );
+        } else {
+            println!(
+                "hint: restore it with 'rgit branch {} {}'",
+                branch_name, tip
+            );
+        }
     }
 
     Ok(())
 }
+
+// Whether the commit is reachable from the other commit (or is that commit)
+fn is_ancestor(commit: &str, descendant: &str) -> io::Result<bool> {
+    Ok(ancestors(&[descendant.to_string()])?.contains(commit))
+}
+
+// Whether HEAD pointed to the commit at some point, according to the HEAD log
+fn in_head_log(hash: &str) -> io::Result<bool> {
+    Ok(read_reflog("HEAD")?
+        .iter()
+        .any(|entry| entry.old == hash || entry.new == hash))
+}
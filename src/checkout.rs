@@ -1,40 +1,401 @@
-use std::{
-    fs::File,
-    io::{self, Write},
-    path::Path,
+use std::{collections::BTreeMap, io, path::Path};
+
+use crate::{
+    branch::{branch, BranchOptions},
+    head::{warn_orphaned, Head},
+    hooks::{run_hook, run_notify_hook, NULL_HASH},
+    object::{peel_to_commit, read_tree, Commit},
+    refs::{read_ref, RefTransaction},
+    revparse::{previous_checkout, resolve_revision, shorten_ref_name},
+    revwalk::path_matches,
+    worktree::{
+        lock_index, read_index, remove_worktree_file, switch_tree, write_blob_file, write_index,
+    },
 };
 
-pub fn checkout(branch_or_commit: &str) -> io::Result<()> {
-    let head_path = ".rgit/HEAD";
+#[derive(Debug, Clone, Default)]
+pub struct CheckoutOptions {
+    // Create the branch (-b) or reset it when it exists (-B) before checking it out
+    pub new_branch: Option<String>,
+    pub force_new_branch: bool,
+    pub no_verify: bool,
+}
 
-    let branch_path = format!(".rgit/refs/heads/{}", branch_or_commit);
+// `checkout [<rev>] [--] [<paths>]`: switch to a branch or commit, or restore files.
+// `paths` holds the arguments after "--", which are never taken as a revision.
+pub fn checkout(
+    args: &[String],
+    paths: Option<&[String]>,
+    options: &CheckoutOptions,
+) -> io::Result<()> {
+    if let Some(new_branch) = &options.new_branch {
+        if paths.is_some() || args.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot update paths and switch to a new branch at the same time",
+            ));
+        }
+        return switch(
+            args.first().map(|a| a.as_str()),
+            &SwitchOptions {
+                create: Some(new_branch.clone()),
+                force_create: options.force_new_branch,
+                no_verify: options.no_verify,
+                ..Default::default()
+            },
+        );
+    }
 
-    // Branch exist, then perform branch switching
-    if Path::new(&branch_path).exists() {
-        let mut head_file = File::create(head_path)?;
-        head_file.write_all(format!("ref: refs/heads/{}", branch_or_commit).as_bytes())?;
+    if let Some(paths) = paths {
+        // Everything before "--" is the revision to take the files from
+        return match args {
+            [] => checkout_paths(None, paths),
+            [rev] => checkout_paths(Some(rev), paths),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Only one revision can be given before '--'",
+            )),
+        };
+    }
 
+    let Some((first, rest)) = args.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Missing branch, commit or path argument",
+        ));
+    };
+
+    // Without "--" the first argument is a revision when it names one, the rest are paths
+    if !is_revision(first) {
+        return checkout_paths(None, args);
+    }
+    if !rest.is_empty() {
+        return checkout_paths(Some(first), rest);
+    }
+    if Path::new(first).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Ambiguous argument '{}': both revision and filename\nUse '--' to separate paths from revisions, like this:\n'rgit checkout <revision> -- <path>'",
+                first
+            ),
+        ));
+    }
+
+    let branch_or_commit = expand_previous(first)?;
+
+    // Branch exist, then perform branch switching
+    if let Some(new_head) = read_ref(&format!("refs/heads/{}", branch_or_commit))? {
+        move_head(
+            Some(&branch_or_commit),
+            &new_head,
+            &branch_or_commit,
+            false,
+            options.no_verify,
+        )?;
         println!("Switched to branch {}", branch_or_commit);
     } else {
-        // Commit check out here
-        let commit_obj_path = format!(
-            ".rgit/objects/{}/{}",
-            &branch_or_commit[0..2],
-            &branch_or_commit[2..]
-        );
+        // Commit check out here, any revision expression naming a commit is accepted
+        let commit = resolve_commit(&branch_or_commit)?;
+        move_head(None, &commit, &branch_or_commit, false, options.no_verify)?;
+        println!("Checked out commit {}", commit);
+    }
+    Ok(())
+}
+
+// Restore files from the index, or from a commit updating the index as well.
+// Unlike `restore`, files missing from the commit are left alone.
+fn checkout_paths(rev: Option<&String>, paths: &[String]) -> io::Result<()> {
+    let restored = restore(
+        paths,
+        &RestoreOptions {
+            source: rev.cloned(),
+            staged: rev.is_some(),
+            worktree: true,
+            overlay: true,
+        },
+    )?;
+
+    let plural = if restored == 1 { "" } else { "s" };
+    match rev {
+        Some(rev) => println!(
+            "Updated {} path{} from {}",
+            restored,
+            plural,
+            &resolve_commit(rev)?[..7]
+        ),
+        None => println!("Updated {} path{} from the index", restored, plural),
+    }
+    Ok(())
+}
+
+// Whether a checkout argument names a branch or a commit
+fn is_revision(name: &str) -> bool {
+    match expand_previous(name) {
+        Ok(name) => {
+            matches!(read_ref(&format!("refs/heads/{}", name)), Ok(Some(_)))
+                || resolve_commit(&name).is_ok()
+        }
+        Err(_) => false,
+    }
+}
 
-        if Path::new(&commit_obj_path).exists() {
-            // Commit obj exist
-            let mut head_file = File::create(head_path)?;
-            // Update HEAD file content to user passed commit
-            head_file.write_all(branch_or_commit.as_bytes())?;
-            println!("Checked out commit {}", branch_or_commit);
-        } else {
+#[derive(Debug, Clone, Default)]
+pub struct SwitchOptions {
+    // Create the branch (-c) or reset it when it exists (-C) before switching to it
+    pub create: Option<String>,
+    pub force_create: bool,
+    // Detach HEAD at the commit instead of switching to a branch
+    pub detach: bool,
+    // Throw away local changes of the files that differ between the commits
+    pub discard_changes: bool,
+    pub no_verify: bool,
+}
+
+// `switch`: move HEAD to a branch (or a detached commit with --detach),
+// updating the working tree and the index to its files
+pub fn switch(target: Option<&str>, options: &SwitchOptions) -> io::Result<()> {
+    if let Some(new_branch) = &options.create {
+        let refname = format!("refs/heads/{}", new_branch);
+        let previous = read_ref(&refname)?;
+        branch(
+            new_branch,
+            &BranchOptions {
+                start_point: target.map(|t| t.to_string()),
+                force: options.force_create,
+            },
+        )?;
+        let new_head = read_ref(&refname)?.unwrap_or_default();
+        if let Err(e) = move_head(
+            Some(new_branch),
+            &new_head,
+            new_branch,
+            options.discard_changes,
+            options.no_verify,
+        ) {
+            // The switch did not happen, the branch goes back to what it was (or away)
+            let mut transaction = RefTransaction::new(&format!("switch: Restored {}", new_branch));
+            match &previous {
+                Some(old) => transaction.update(&refname, old, Some(&new_head), false),
+                None => transaction.delete(&refname, Some(&new_head), false),
+            }
+            transaction.commit()?;
+            return Err(e);
+        }
+        println!("Switched to a new branch {}", new_branch);
+        return Ok(());
+    }
+
+    let target = target.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Missing branch or commit argument",
+        )
+    })?;
+    let target = expand_previous(target)?;
+
+    if options.detach {
+        let commit = resolve_commit(&target)?;
+        move_head(
+            None,
+            &commit,
+            &target,
+            options.discard_changes,
+            options.no_verify,
+        )?;
+        let subject = Commit::read(&commit)?.subject().to_string();
+        println!("HEAD is now at {} {}", &commit[..7], subject);
+        return Ok(());
+    }
+
+    let Some(new_head) = read_ref(&format!("refs/heads/{}", target))? else {
+        return Err(match resolve_commit(&target) {
+            Ok(_) => io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "A branch is expected, got '{}'\nhint: use 'rgit switch --detach {}' to check out a commit",
+                    target, target
+                ),
+            ),
+            Err(_) => io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Invalid reference: {}", target),
+            ),
+        });
+    };
+
+    let current = Head::read()?;
+    if !options.discard_changes && current.branch() == Some(target.as_str()) {
+        println!("Already on {}", target);
+        return Ok(());
+    }
+
+    move_head(
+        Some(&target),
+        &new_head,
+        &target,
+        options.discard_changes,
+        options.no_verify,
+    )?;
+    println!("Switched to branch {}", target);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    // Revision to take the files from, the index (or HEAD with --staged) by default
+    pub source: Option<String>,
+    pub staged: bool,
+    pub worktree: bool,
+    // Keep files missing from the source instead of removing them
+    pub overlay: bool,
+}
+
+// `restore`: discard changes of working tree files and/or unstage index entries,
+// returning the number of files restored
+pub fn restore(paths: &[String], options: &RestoreOptions) -> io::Result<usize> {
+    // Without --staged only the working tree is restored
+    let worktree = options.worktree || !options.staged;
+    // The index is only rewritten when restoring staged files
+    let lock = if options.staged {
+        Some(lock_index()?)
+    } else {
+        None
+    };
+    let mut index = read_index()?;
+
+    let source: BTreeMap<String, String> = match &options.source {
+        Some(rev) => read_tree(&Commit::read(&resolve_commit(rev)?)?.tree)?,
+        None if options.staged => match Head::read()?.commit()? {
+            Some(head) => read_tree(&Commit::read(&head)?.tree)?,
+            None => BTreeMap::new(),
+        },
+        None => index.clone(),
+    };
+
+    // Files known to the source or to the index, a missing source file gets removed
+    let mut known: Vec<&String> = if options.overlay {
+        source.keys().collect()
+    } else {
+        source.keys().chain(index.keys()).collect()
+    };
+    known.sort();
+    known.dedup();
+    for spec in paths {
+        let spec = spec.trim_start_matches("./");
+        if !known
+            .iter()
+            .any(|path| path_matches(path, &[spec.to_string()]))
+        {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
-                "Error: branch or commit not found",
+                format!(
+                    "pathspec '{}' did not match any file(s) known to rgit",
+                    spec
+                ),
             ));
         }
     }
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|p| p.trim_start_matches("./").to_string())
+        .collect();
+    let selected: Vec<String> = known
+        .into_iter()
+        .filter(|path| path_matches(path, &paths))
+        .cloned()
+        .collect();
+
+    for path in &selected {
+        let hash = source.get(path);
+        if options.staged {
+            match hash {
+                Some(hash) => index.insert(path.clone(), hash.clone()),
+                None => index.remove(path),
+            };
+        }
+        if worktree {
+            match hash {
+                Some(hash) => write_blob_file(path, hash)?,
+                None => remove_worktree_file(path)?,
+            }
+        }
+    }
+    if let Some(lock) = lock {
+        write_index(lock, &index)?;
+    }
+    Ok(selected.len())
+}
+
+// "-" and "@{-<n>}" name the branch (or commit) checked out before
+fn expand_previous(name: &str) -> io::Result<String> {
+    if name == "-" {
+        return previous_checkout(1);
+    }
+    match name
+        .strip_prefix("@{-")
+        .and_then(|n| n.strip_suffix('}'))
+        .and_then(|n| n.parse().ok())
+    {
+        Some(n) => previous_checkout(n),
+        None => Ok(name.to_string()),
+    }
+}
+
+// Commit named by a revision expression
+fn resolve_commit(name: &str) -> io::Result<String> {
+    resolve_revision(name)
+        .and_then(|hash| peel_to_commit(&hash))
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(io::ErrorKind::NotFound, "Error: branch or commit not found")
+            }
+            // e.g. an ambiguous abbreviated hash
+            _ => e,
+        })
+}
+
+// Point HEAD at a branch (or detach it at `commit`), bringing the working tree and
+// the index to the files of `commit` and recording the move in the HEAD log
+fn move_head(
+    branch: Option<&str>,
+    commit: &str,
+    label: &str,
+    force: bool,
+    no_verify: bool,
+) -> io::Result<()> {
+    let head = Head::read()?;
+    let old_commit = head.commit()?;
+    // Commit HEAD currently points to, passed to the checkout hooks
+    let previous_head = old_commit.clone().unwrap_or(NULL_HASH.to_string());
+    // Branch or detached commit we move from, recorded in the HEAD log for "@{-<n>}"
+    let moving_from = match head.refname() {
+        Some(branch_ref) => shorten_ref_name(branch_ref).to_string(),
+        None => previous_head.clone(),
+    };
+
+    if !no_verify {
+        run_hook("pre-checkout", &[&previous_head, commit, "1"], &[])?;
+    }
+
+    let old_tree = match &old_commit {
+        Some(old) => read_tree(&Commit::read(old)?.tree)?,
+        None => BTreeMap::new(),
+    };
+    let new_tree = read_tree(&Commit::read(commit)?.tree)?;
+    switch_tree(&old_tree, &new_tree, force)?;
+
+    // Commits made on a detached HEAD are lost when nothing else points to them
+    if head.is_detached() && old_commit.as_deref() != Some(commit) {
+        warn_orphaned(&previous_head, commit)?;
+    }
+
+    let message = format!("checkout: moving from {} to {}", moving_from, label);
+    match branch {
+        Some(name) => Head::Symbolic(format!("refs/heads/{}", name)).write(&message)?,
+        None => Head::Detached(commit.to_string()).write(&message)?,
+    }
+
+    run_notify_hook("post-checkout", &[&previous_head, commit, "1"], &[]);
     Ok(())
 }
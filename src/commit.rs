@@ -1,16 +1,35 @@
 use std::{
-    fs::File,
-    io::{self, Write},
+    fs, io,
     path::Path,
     time::{SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
     config::get_config,
-    helper::{create_tree, get_current_ref_branch, get_parent_commit, hash_and_store_obj},
+    head::Head,
+    helper::{create_tree, hash_and_store_obj},
+    hooks::{run_hook, run_notify_hook},
+    object::read_object_as,
+    revparse::resolve_revision,
+    signing::{embed_signature, sign_payload, verify_object},
+    trailer::{add_trailers, parse_trailer_arg, TrailerOptions},
 };
 
-pub fn commit(message: &str) -> io::Result<()> {
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    // Add a "Signed-off-by" trailer for the committer
+    pub signoff: bool,
+    // Extra trailers given as "token=value"
+    pub trailers: Vec<String>,
+    // Sign the commit with an SSH key
+    pub sign: bool,
+    // Key file used for signing instead of `user.signingKey`
+    pub signing_key: Option<String>,
+    // Skip the pre-commit and commit-msg hooks
+    pub no_verify: bool,
+}
+
+pub fn commit(message: &str, options: &CommitOptions) -> io::Result<()> {
     let index_path = ".rgit/index";
     // Check changes file to commit
     if !Path::new(index_path).exists() {
@@ -18,44 +37,133 @@ pub fn commit(message: &str) -> io::Result<()> {
         return Ok(());
     }
 
-    // Create the tree object and get back the tree hash value
-    let tree_hash = create_tree()?;
-
     // Get the current time
     // TODO :: Change to human readable date
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        .map_err(io::Error::other)?
         .as_secs();
 
-    // Get the parent commit (if any)
-    let parent_commit = get_parent_commit()?;
-
     // Get author name and email form configuration
     let author_name = get_config("[user]", "name")?.unwrap_or("default".to_string());
     let author_email = get_config("[user]", "email")?.unwrap_or("default@email.com".to_string());
+    let hook_env = [
+        ("GIT_AUTHOR_NAME", author_name.clone()),
+        ("GIT_AUTHOR_EMAIL", author_email.clone()),
+        ("GIT_AUTHOR_DATE", format!("@{} +0000", now)),
+    ];
+
+    if !options.no_verify {
+        run_hook("pre-commit", &[], &hook_env)?;
+    }
+
+    // Create the tree object and get back the tree hash value
+    let tree_hash = create_tree()?;
+
+    // Get the parent commit (if any), HEAD may be detached
+    let head = Head::read()?;
+    let parent_commit = head.commit()?;
+
+    // Append trailers requested with --trailer and --signoff to the message
+    let mut trailers = options
+        .trailers
+        .iter()
+        .map(|t| parse_trailer_arg(t))
+        .collect::<io::Result<Vec<_>>>()?;
+    if options.signoff {
+        trailers.push((
+            "Signed-off-by".to_string(),
+            format!("{} <{}>", author_name, author_email),
+        ));
+    }
+    let message = if trailers.is_empty() {
+        message.to_string()
+    } else {
+        add_trailers(message, &trailers, &TrailerOptions::default())?
+            .trim_end()
+            .to_string()
+    };
+
+    // Let prepare-commit-msg and commit-msg hooks inspect or edit the message
+    let message_path = ".rgit/COMMIT_EDITMSG";
+    fs::write(message_path, format!("{}\n", message))?;
+    run_hook("prepare-commit-msg", &[message_path, "message"], &hook_env)?;
+    if !options.no_verify {
+        run_hook("commit-msg", &[message_path], &hook_env)?;
+    }
+    let message = fs::read_to_string(message_path)?.trim_end().to_string();
+    if message.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Aborting commit due to empty commit message",
+        ));
+    }
 
     // Create the commit object contents
     let mut commit_contents = format!(
-        "Tree: {}\nAuthor: {} <{}> {} +0000\nMessage: {}",
-        tree_hash, author_name, author_email, now, message
+        "Tree: {}\nAuthor: {} <{}> {} +0000\nCommitter: {} <{}> {} +0000\nMessage: {}",
+        tree_hash, author_name, author_email, now, author_name, author_email, now, message
     );
 
     // If parent commit exist, then add to commit content
-    if let Some(parent) = parent_commit {
+    if let Some(parent) = &parent_commit {
         commit_contents = format!("Parent: {}\n{}", parent, commit_contents);
     }
 
+    // Sign the commit contents when requested with -S or `commit.gpgSign`
+    let sign = options.sign
+        || get_config("[commit]", "gpgSign")?.is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    if sign {
+        let signature = sign_payload(&commit_contents, options.signing_key.as_deref())?;
+        commit_contents = embed_signature(&commit_contents, &signature);
+    }
+
     let commit_hash = hash_and_store_obj("commit", &commit_contents)?;
 
-    // Create the reference file or update to link with current commit
-    let branch_ref = get_current_ref_branch()?.unwrap();
-    let branch_ref_path = format!(".rgit/{}", branch_ref);
+    // Create the reference file or update to link with current commit (or move a detached HEAD)
+    // and record it in the reflog, e.g. "commit (initial): First commit"
+    let subject = message.lines().next().unwrap_or_default();
+    let reflog_message = match &parent_commit {
+        Some(_) => format!("commit: {}", subject),
+        None => format!("commit (initial): {}", subject),
+    };
+    head.advance(&commit_hash, &reflog_message)?;
 
-    let mut branch_ref_file = File::create(&branch_ref_path)?;
+    if head.is_detached() {
+        println!("Committed on detached HEAD with: {}", commit_hash);
+    } else {
+        println!("Committed with: {}", commit_hash);
+    }
 
-    branch_ref_file.write_all(&commit_hash.as_bytes())?;
+    run_notify_hook("post-commit", &[], &hook_env);
+    Ok(())
+}
+
+// Check the SSH signature of commits
+pub fn verify_commit(names: &[String]) -> io::Result<()> {
+    let mut all_trusted = true;
+
+    for name in names {
+        let commit_hash = resolve_revision(name)?;
+        let contents = read_object_as(&commit_hash, "commit")?;
 
-    println!("Committed with: {}", commit_hash);
+        match verify_object(&contents)? {
+            Some(check) => {
+                println!("{}", check.describe());
+                all_trusted &= check.is_trusted();
+            }
+            None => {
+                println!("Commit {} does not have a signature", commit_hash);
+                all_trusted = false;
+            }
+        }
+    }
+
+    if !all_trusted {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "signature verification failed",
+        ));
+    }
     Ok(())
 }
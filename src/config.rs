@@ -1,59 +1,60 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
 };
 
+use crate::lockfile::LockFile;
+
 // Configuration command
 fn set_config(section_name: &str, key: &str, value: &str) -> io::Result<()> {
-    let config_file_path = ".rgit/config"; // config file path
+    // Hold the config lock while reading and rewriting the file
+    let lock = LockFile::acquire(".rgit/config")?;
 
     // Read config file if it exist and get value and store them in lines String Vector
-    let mut lines: Vec<String> = vec![];
-    if let Ok(config_file) = File::open(config_file_path) {
-        let config_file_rdr = BufReader::new(config_file);
-        for line in config_file_rdr.lines() {
-            lines.push(line?);
-        }
-    }
+    let mut lines = read_config_lines()?;
 
-    // Find or add '[user]' section and if already have user section then update the value according to key
-    let mut is_user_section = false;
+    // Find or add the section and if the key already exist then update the value
+    let mut in_section = false;
+    let mut section_end: Option<usize> = None;
     let mut updated = false;
 
     // Loop through lines of config file
-    for line in lines.iter_mut() {
-        if line.trim() == section_name {
-            is_user_section = true;
-        } else if is_user_section && line.trim().starts_with(key) {
-            *line = format!("    {} = {}", key, value); // Update the value when match with key
-            updated = true;
-            break;
-        } else if line.trim().is_empty() {
-            is_user_section = false;
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_section = trimmed == section_name;
+            if in_section {
+                section_end = Some(i + 1);
+            }
+        } else if in_section {
+            if let Some((config_key, _)) = trimmed.split_once('=') {
+                if config_key.trim().eq_ignore_ascii_case(key) {
+                    *line = format!("    {} = {}", key, value); // Update the value when match with key
+                    updated = true;
+                    break;
+                }
+            }
+            if !trimmed.is_empty() {
+                section_end = Some(i + 1);
+            }
         }
     }
 
-    // Handle case for new config file or config that does not have '[user]' section
+    // Handle case for new config file or config that does not have the section yet
     if !updated {
-        if !is_user_section {
-            lines.push("[user]".to_string());
+        let new_line = format!("    {} = {}", key, value);
+        match section_end {
+            Some(pos) => lines.insert(pos, new_line),
+            None => {
+                lines.push(section_name.to_string());
+                lines.push(new_line);
+            }
         }
-        // Add new config for user
-        lines.push(format!("    {} = {}", key, value));
     }
 
     // Write new update config file back to .rgit/config file
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(config_file_path)?;
-
-    for line in lines {
-        writeln!(file, "{}", line)?;
-    }
-
-    Ok(())
+    write_config_lines(lock, &lines)
 }
 
 pub fn get_config(section_name: &str, key: &str) -> io::Result<Option<String>> {
@@ -62,22 +63,22 @@ pub fn get_config(section_name: &str, key: &str) -> io::Result<Option<String>> {
     if let Ok(config_file) = File::open(config_file_path) {
         // Create buffer to read line by line to config file content
         let config_file_rdr = BufReader::new(config_file);
-        let mut in_user_section = true; // Indicator for '[user]' section
+        let mut in_section = false; // Indicator for the requested section
 
         for line in config_file_rdr.lines() {
             let line = line?;
-            if line.trim() == section_name {
-                in_user_section = true // By defining in_user_section indicator to true make sure below content will be associated with '[user]' section
-            } else if in_user_section {
-                // Split string by " = "
-                if let Some((config_key, config_value)) = line.trim().split_once(" = ") {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                // By defining in_section indicator make sure below content will be associated with the section
+                in_section = trimmed == section_name;
+            } else if in_section {
+                // Split string by "="
+                if let Some((config_key, config_value)) = trimmed.split_once('=') {
                     // Match with user provided key
-                    if config_key == key {
-                        return Ok(Some(config_value.to_string()));
+                    if config_key.trim().eq_ignore_ascii_case(key) {
+                        return Ok(Some(unquote(config_value.trim())));
                     }
                 }
-            } else if line.trim().is_empty() {
-                in_user_section = false;
             }
         }
     }
@@ -85,6 +86,150 @@ pub fn get_config(section_name: &str, key: &str) -> io::Result<Option<String>> {
     Ok(None)
 }
 
+// Lines of the config file, empty when there is none
+fn read_config_lines() -> io::Result<Vec<String>> {
+    match File::open(".rgit/config") {
+        Ok(config_file) => BufReader::new(config_file).lines().collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+// Replace the config file with new lines through its lock
+fn write_config_lines(mut lock: LockFile, lines: &[String]) -> io::Result<()> {
+    let contents: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    lock.write_all(contents.as_bytes())?;
+    lock.commit()
+}
+
+// Entries of a section, without its header
+fn section_entries(lines: &[String], section_name: &str) -> Vec<String> {
+    let mut in_section = false;
+    lines
+        .iter()
+        .filter(|line| {
+            if line.trim().starts_with('[') {
+                in_section = line.trim() == section_name;
+                return false;
+            }
+            in_section && !line.trim().is_empty()
+        })
+        .cloned()
+        .collect()
+}
+
+// Lines of the config file without a section and its entries
+fn without_section(lines: Vec<String>, section_name: &str) -> Vec<String> {
+    let mut in_section = false;
+    lines
+        .into_iter()
+        .filter(|line| {
+            if line.trim().starts_with('[') {
+                in_section = line.trim() == section_name;
+            }
+            !in_section
+        })
+        .collect()
+}
+
+// Remove a whole section (e.g. `[branch "topic"]`) with its entries
+pub fn remove_config_section(section_name: &str) -> io::Result<()> {
+    if !Path::new(".rgit/config").exists() {
+        return Ok(());
+    }
+    let lock = LockFile::acquire(".rgit/config")?;
+    let lines = without_section(read_config_lines()?, section_name);
+    write_config_lines(lock, &lines)
+}
+
+// Rename a section, replacing the entries of a section with the new name
+pub fn rename_config_section(section_name: &str, new_name: &str) -> io::Result<()> {
+    move_config_section(section_name, new_name, false)
+}
+
+// Copy the entries of a section to a section with another name, replacing its entries
+pub fn copy_config_section(section_name: &str, new_name: &str) -> io::Result<()> {
+    move_config_section(section_name, new_name, true)
+}
+
+fn move_config_section(section_name: &str, new_name: &str, keep: bool) -> io::Result<()> {
+    if section_name == new_name || !Path::new(".rgit/config").exists() {
+        return Ok(());
+    }
+    let lock = LockFile::acquire(".rgit/config")?;
+    let lines = read_config_lines()?;
+    let entries = section_entries(&lines, section_name);
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = without_section(lines, new_name);
+    if !keep {
+        lines = without_section(lines, section_name);
+    }
+    lines.push(new_name.to_string());
+    lines.extend(entries);
+    write_config_lines(lock, &lines)
+}
+
+// Split a dotted configuration name into its section header and key
+// e.g. "name" -> ("[user]", "name"), "core.hooksPath" -> ("[core]", "hooksPath"),
+// "trailer.sign.key" -> ("[trailer \"sign\"]", "key")
+pub fn parse_config_key(name: &str) -> (String, String) {
+    match (name.find('.'), name.rfind('.')) {
+        (Some(first), Some(last)) if first == last => (
+            format!("[{}]", &name[..first]),
+            name[last + 1..].to_string(),
+        ),
+        (Some(first), Some(last)) => (
+            format!("[{} \"{}\"]", &name[..first], &name[first + 1..last]),
+            name[last + 1..].to_string(),
+        ),
+        _ => ("[user]".to_string(), name.to_string()),
+    }
+}
+
+// List every configuration entry as (dotted name, value) pairs, in file order
+// e.g. `[trailer "sign"]` with `key = Signed-off-by` gives ("trailer.sign.key", "Signed-off-by")
+pub fn list_config() -> io::Result<Vec<(String, String)>> {
+    let config_file_path = ".rgit/config";
+    let mut entries = vec![];
+
+    if let Ok(config_file) = File::open(config_file_path) {
+        let config_file_rdr = BufReader::new(config_file);
+        let mut section_prefix = String::new();
+
+        for line in config_file_rdr.lines() {
+            let line = line?;
+            let trimmed = line.trim();
+            if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+                section_prefix = match header.split_once(' ') {
+                    Some((section, subsection)) => {
+                        format!("{}.{}", section, subsection.trim().trim_matches('"'))
+                    }
+                    None => header.to_string(),
+                };
+            } else if let Some((config_key, config_value)) = trimmed.split_once('=') {
+                entries.push((
+                    format!("{}.{}", section_prefix, config_key.trim()),
+                    unquote(config_value.trim()),
+                ));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+// Remove surrounding double quotes of a configuration value
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
 // Handle config commands (set or get)
 pub fn handle_config_command(
     action: &str,
@@ -118,12 +263,12 @@ pub fn handle_config_command(
 
 // TODO:: Update logic and handle validation for more effective
 pub fn add_remote(name: &str, url: &str) -> io::Result<()> {
-    let mut config_file = File::options()
-        .create(true)
-        .append(true)
-        .open(".rgit/config")?;
-
-    writeln!(config_file, "[remote \"{}\"]\n    url = {}\n", name, url)?;
+    let lock = LockFile::acquire(".rgit/config")?;
+    let mut lines = read_config_lines()?;
+    lines.push(format!("[remote \"{}\"]", name));
+    lines.push(format!("    url = {}", url));
+    lines.push(String::new());
+    write_config_lines(lock, &lines)?;
     println!("Remote '{}' added", name);
     Ok(())
 }
@@ -1,10 +1,16 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fs::File,
     io::{self, BufRead, BufReader},
     path::Path,
 };
 
-use crate::helper::compute_file_hash;
+use regex::Regex;
+
+use crate::{
+    helper::compute_file_hash,
+    object::{read_object, read_tree, Commit},
+};
 
 // TODO: Implement full logic for diff command
 pub fn diff() -> io::Result<()> {
@@ -31,3 +37,499 @@ pub fn diff() -> io::Result<()> {
 
     Ok(())
 }
+
+// One step of an edit script turning the old lines into the new lines (0-based line indexes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// Half of the edit distance the middle snake search goes up to; beyond it the lines
+// are reported as all deleted and all inserted to bound the running time
+const MAX_SNAKE_COST: usize = 4096;
+
+// Shortest edit script between two sequences of lines (Myers' algorithm in linear space)
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, 0, 0, &mut edits);
+    edits
+}
+
+// Edits of a part of the sequences starting at old line `x` and new line `y`: split at the
+// middle snake of a shortest edit script and recurse on both sides
+fn diff_range<T: PartialEq>(old: &[T], new: &[T], x: usize, y: usize, edits: &mut Vec<Edit>) {
+    // Common leading and trailing lines are matched directly
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    edits.extend((0..prefix).map(|i| Edit::Equal(x + i, y + i)));
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    let (x_mid, y_mid) = (x + prefix, y + prefix);
+
+    if old_mid.is_empty() || new_mid.is_empty() {
+        edits.extend((0..old_mid.len()).map(|i| Edit::Delete(x_mid + i)));
+        edits.extend((0..new_mid.len()).map(|i| Edit::Insert(y_mid + i)));
+    } else if let Some((start_x, start_y, end_x, end_y)) = middle_snake(old_mid, new_mid) {
+        diff_range(
+            &old_mid[..start_x],
+            &new_mid[..start_y],
+            x_mid,
+            y_mid,
+            edits,
+        );
+        edits.extend(
+            (0..end_x - start_x).map(|i| Edit::Equal(x_mid + start_x + i, y_mid + start_y + i)),
+        );
+        diff_range(
+            &old_mid[end_x..],
+            &new_mid[end_y..],
+            x_mid + end_x,
+            y_mid + end_y,
+            edits,
+        );
+    } else {
+        // Too many differences to search for a shortest script
+        edits.extend((0..old_mid.len()).map(|i| Edit::Delete(x_mid + i)));
+        edits.extend((0..new_mid.len()).map(|i| Edit::Insert(y_mid + i)));
+    }
+
+    let (x_end, y_end) = (x + old.len() - suffix, y + new.len() - suffix);
+    edits.extend((0..suffix).map(|i| Edit::Equal(x_end + i, y_end + i)));
+}
+
+// Snake (run of equal lines) in the middle of a shortest edit script, found by searching
+// forward from the start and backward from the end until the paths overlap. Returns its
+// start and end points, None when the edit distance goes beyond MAX_SNAKE_COST.
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> Option<(usize, usize, usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max_d = ((n + m + 1) / 2).min(MAX_SNAKE_COST as isize);
+    let offset = max_d + 1;
+    // Furthest reaching x on every diagonal k = x - y, forward from the start and
+    // backward from the end (in coordinates counted from the end)
+    let mut forward = vec![0isize; 2 * max_d as usize + 3];
+    let mut backward = vec![0isize; 2 * max_d as usize + 3];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max_d {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (start_x, start_y) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            // The backward search has made d - 1 steps, on diagonal delta - k
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[at(reverse_k)] >= n {
+                return Some((start_x as usize, start_y as usize, x as usize, y as usize));
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut u = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut v = u - k;
+            let (end_u, end_v) = (u, v);
+            while u < n && v < m && old[(n - u - 1) as usize] == new[(m - v - 1) as usize] {
+                u += 1;
+                v += 1;
+            }
+            backward[at(k)] = u;
+            // Both searches have made d steps, the forward one on diagonal delta - k
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && u + forward[at(forward_k)] >= n {
+                return Some((
+                    (n - u) as usize,
+                    (m - v) as usize,
+                    (n - end_u) as usize,
+                    (m - end_v) as usize,
+                ));
+            }
+        }
+    }
+    None
+}
+
+// Group the edits into hunks with `context` unchanged lines around the changes
+pub fn format_hunks(old: &[&str], new: &[&str], edits: &[Edit], context: usize) -> String {
+    // Number of old and new lines before every edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for edit in edits {
+        positions.push((old_pos, new_pos));
+        match edit {
+            Edit::Equal(..) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Delete(_) => old_pos += 1,
+            Edit::Insert(_) => new_pos += 1,
+        }
+    }
+    positions.push((old_pos, new_pos));
+
+    // Ranges of edits shown together, merged when their context overlaps
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for (i, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Equal(..)) {
+            continue;
+        }
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let (old_start, new_start) = positions[start];
+            format_hunk(old, new, &edits[start..end], old_start, new_start)
+        })
+        .collect()
+}
+
+// Format one hunk, starting after `old_start` old lines and `new_start` new lines
+pub fn format_hunk(
+    old: &[&str],
+    new: &[&str],
+    edits: &[Edit],
+    old_start: usize,
+    new_start: usize,
+) -> String {
+    let old_len = edits
+        .iter()
+        .filter(|e| !matches!(e, Edit::Insert(_)))
+        .count();
+    let new_len = edits
+        .iter()
+        .filter(|e| !matches!(e, Edit::Delete(_)))
+        .count();
+    let mut output = format!(
+        "@@ -{} +{} @@\n",
+        hunk_range(old_start, old_len),
+        hunk_range(new_start, new_len)
+    );
+
+    for edit in edits {
+        let (prefix, line) = match *edit {
+            Edit::Equal(x, _) => (' ', old[x]),
+            Edit::Delete(x) => ('-', old[x]),
+            Edit::Insert(y) => ('+', new[y]),
+        };
+        output.push(prefix);
+        output.push_str(line);
+        if !line.ends_with('\n') {
+            output.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+
+    output
+}
+
+// Hunk header range: "start,length", the length is omitted when it is 1
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+// Split a text into lines, keeping the line endings
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+// Change of one file between two trees, None for a missing side
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+// Files that differ between two trees (path -> blob hash)
+pub fn diff_trees(
+    old_tree: &BTreeMap<String, String>,
+    new_tree: &BTreeMap<String, String>,
+) -> Vec<FileChange> {
+    let paths: BTreeSet<&String> = old_tree.keys().chain(new_tree.keys()).collect();
+    paths
+        .into_iter()
+        .filter(|path| old_tree.get(*path) != new_tree.get(*path))
+        .map(|path| FileChange {
+            path: path.clone(),
+            old: old_tree.get(path).cloned(),
+            new: new_tree.get(path).cloned(),
+        })
+        .collect()
+}
+
+// Changes introduced by a commit compared to its first parent
+// Merge commits have no changes of their own, like `git log` without `-m`
+pub fn commit_changes(commit: &Commit) -> io::Result<Vec<FileChange>> {
+    if commit.parents.len() > 1 {
+        return Ok(vec![]);
+    }
+    let old_tree = match commit.parents.first() {
+        Some(parent) => read_tree(&Commit::read(parent)?.tree)?,
+        None => BTreeMap::new(),
+    };
+    Ok(diff_trees(&old_tree, &read_tree(&commit.tree)?))
+}
+
+// Contents of a blob, or an empty text for a missing side of a change
+pub fn read_blob_text(hash: Option<&str>) -> io::Result<String> {
+    match hash {
+        Some(hash) => {
+            let (_, contents) = read_object(hash)?;
+            Ok(String::from_utf8_lossy(&contents).into_owned())
+        }
+        None => Ok(String::new()),
+    }
+}
+
+// Unified diff of one file change, in the format of `git diff`
+pub fn format_file_patch(change: &FileChange) -> io::Result<String> {
+    let old_text = read_blob_text(change.old.as_deref())?;
+    let new_text = read_blob_text(change.new.as_deref())?;
+    let abbrev = |hash: &Option<String>| match hash {
+        Some(hash) => hash[..7.min(hash.len())].to_string(),
+        None => "0000000".to_string(),
+    };
+
+    let mut output = format!("diff --git a/{0} b/{0}\n", change.path);
+    match (&change.old, &change.new) {
+        (None, _) => output.push_str("new file mode 100644\n"),
+        (_, None) => output.push_str("deleted file mode 100644\n"),
+        _ => {}
+    }
+    output.push_str(&format!(
+        "index {}..{}{}\n",
+        abbrev(&change.old),
+        abbrev(&change.new),
+        if change.old.is_some() && change.new.is_some() {
+            " 100644"
+        } else {
+            ""
+        }
+    ));
+
+    let old_name = match change.old {
+        Some(_) => format!("a/{}", change.path),
+        None => "/dev/null".to_string(),
+    };
+    let new_name = match change.new {
+        Some(_) => format!("b/{}", change.path),
+        None => "/dev/null".to_string(),
+    };
+    if old_text.contains('\0') || new_text.contains('\0') {
+        output.push_str(&format!(
+            "Binary files {} and {} differ\n",
+            old_name, new_name
+        ));
+        return Ok(output);
+    }
+
+    let (old_lines, new_lines) = (split_lines(&old_text), split_lines(&new_text));
+    let edits = diff_lines(&old_lines, &new_lines);
+    output.push_str(&format!("--- {}\n+++ {}\n", old_name, new_name));
+    output.push_str(&format_hunks(&old_lines, &new_lines, &edits, 3));
+    Ok(output)
+}
+
+// Pickaxe search of `log -S` and `log -G`
+#[derive(Debug, Clone)]
+pub enum Pickaxe {
+    // The number of occurrences of the string changes
+    Occurrences(Regex),
+    // An added or removed line matches the regular expression
+    Lines(Regex),
+}
+
+impl Pickaxe {
+    pub fn matches(&self, old_text: &str, new_text: &str) -> bool {
+        match self {
+            Pickaxe::Occurrences(pattern) => {
+                pattern.find_iter(old_text).count() != pattern.find_iter(new_text).count()
+            }
+            Pickaxe::Lines(pattern) => {
+                let (old_lines, new_lines) = (split_lines(old_text), split_lines(new_text));
+                diff_lines(&old_lines, &new_lines)
+                    .into_iter()
+                    .any(|edit| match edit {
+                        Edit::Delete(x) => pattern.is_match(old_lines[x]),
+                        Edit::Insert(y) => pattern.is_match(new_lines[y]),
+                        Edit::Equal(..) => false,
+                    })
+            }
+        }
+    }
+
+    // Whether the file change matches the pickaxe
+    pub fn matches_change(&self, change: &FileChange) -> io::Result<bool> {
+        Ok(self.matches(
+            &read_blob_text(change.old.as_deref())?,
+            &read_blob_text(change.new.as_deref())?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Check that the edits turn `old` into `new`, and return the number of equal lines
+    fn apply<T: PartialEq + std::fmt::Debug>(old: &[T], new: &[T], edits: &[Edit]) -> usize {
+        let (mut x, mut y, mut equal) = (0, 0, 0);
+        for edit in edits {
+            match *edit {
+                Edit::Equal(a, b) => {
+                    assert_eq!((a, b), (x, y));
+                    assert_eq!(old[a], new[b]);
+                    x += 1;
+                    y += 1;
+                    equal += 1;
+                }
+                Edit::Delete(a) => {
+                    assert_eq!(a, x);
+                    x += 1;
+                }
+                Edit::Insert(b) => {
+                    assert_eq!(b, y);
+                    y += 1;
+                }
+            }
+        }
+        assert_eq!((x, y), (old.len(), new.len()));
+        equal
+    }
+
+    // Length of the longest common subsequence, computed the quadratic way
+    fn lcs_len<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+        let mut row = vec![0; new.len() + 1];
+        for a in old {
+            let mut diagonal = 0;
+            for (j, b) in new.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if a == b {
+                    diagonal + 1
+                } else {
+                    above.max(row[j])
+                };
+                diagonal = above;
+            }
+        }
+        row[new.len()]
+    }
+
+    #[test]
+    fn myers_paper_example() {
+        let old: Vec<char> = "ABCABBA".chars().collect();
+        let new: Vec<char> = "CBABAC".chars().collect();
+        let edits = diff_lines(&old, &new);
+        assert_eq!(apply(&old, &new, &edits), 4);
+        assert_eq!(edits.len(), 9);
+    }
+
+    #[test]
+    fn empty_and_identical_sides() {
+        let lines = ["a\n", "b\n"];
+        assert_eq!(
+            diff_lines(&lines, &lines),
+            vec![Edit::Equal(0, 0), Edit::Equal(1, 1)]
+        );
+        assert_eq!(
+            diff_lines(&[], &lines),
+            vec![Edit::Insert(0), Edit::Insert(1)]
+        );
+        assert_eq!(
+            diff_lines(&lines, &[]),
+            vec![Edit::Delete(0), Edit::Delete(1)]
+        );
+        assert!(diff_lines::<&str>(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn deletions_come_before_insertions() {
+        assert_eq!(
+            diff_lines(&["a", "b", "c"], &["a", "x", "c"]),
+            vec![
+                Edit::Equal(0, 0),
+                Edit::Delete(1),
+                Edit::Insert(1),
+                Edit::Equal(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_are_shortest() {
+        // Small alphabets give many common subsequences to choose from
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..500 {
+            let old: Vec<u64> = (0..next(30)).map(|_| next(4)).collect();
+            let new: Vec<u64> = (0..next(30)).map(|_| next(4)).collect();
+            let edits = diff_lines(&old, &new);
+            assert_eq!(
+                apply(&old, &new, &edits),
+                lcs_len(&old, &new),
+                "{:?} {:?}",
+                old,
+                new
+            );
+        }
+    }
+
+    #[test]
+    fn costly_differences_stay_valid() {
+        let old: Vec<usize> = (0..3 * MAX_SNAKE_COST).collect();
+        let new: Vec<usize> = (0..3 * MAX_SNAKE_COST).map(|i| i + 100_000).collect();
+        let edits = diff_lines(&old, &new);
+        assert_eq!(apply(&old, &new, &edits), 0);
+    }
+
+    #[test]
+    fn hunks_with_context() {
+        let old = split_lines("1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+        let new = split_lines("1\n2\nthree\n4\n5\n6\n7\n8\n9\nten");
+        let edits = diff_lines(&old, &new);
+        assert_eq!(
+            format_hunks(&old, &new, &edits, 1),
+            "@@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n@@ -9 +9,2 @@\n 9\n+ten\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            format_hunks(&old, &new, &edits, 3),
+            "@@ -1,9 +1,10 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n 7\n 8\n 9\n+ten\n\\ No newline at end of file\n"
+        );
+    }
+}
@@ -0,0 +1,316 @@
+// `for-each-ref`: list references with a format of "%(<field>)" placeholders, sorted and
+// filtered by patterns
+//   %(refname), %(refname:short), %(refname:lstrip=<n>), %(symref), %(HEAD)
+//   %(objectname), %(objectname:short), %(objecttype)
+//   %(upstream), %(upstream:short)
+//   %(subject), %(body), %(contents)
+//   %(<who>name), %(<who>email), %(<who>date[:<mode>]) for author, committer, tagger and creator
+//   %(*<field>) reads the field from the object an annotated tag points to
+// "%%" is a literal '%' and "%xx" a character given as two hex digits
+
+use std::io;
+
+use crate::{
+    branch::branch_upstream,
+    head::Head,
+    object::{read_object, Commit, Identity, Tag},
+    pretty::{format_date, DateMode},
+    refs::{list_refs, read_raw_ref, RefValue},
+    revparse::{glob_regex, shorten_ref_name},
+};
+
+const DEFAULT_FORMAT: &str = "%(objectname) %(objecttype)\t%(refname)";
+
+#[derive(Debug, Clone, Default)]
+pub struct ForEachRefOptions {
+    pub format: Option<String>,
+    // Sort keys, the last one given is the primary key ('-' prefix for descending order)
+    pub sort: Vec<String>,
+    // Stop after this many references
+    pub count: Option<usize>,
+    // Reference prefixes (e.g. "refs/heads") or globs
+    pub patterns: Vec<String>,
+}
+
+// Object a reference points to
+struct RefObject {
+    hash: String,
+    kind: String,
+    commit: Option<Commit>,
+    tag: Option<Tag>,
+}
+
+impl RefObject {
+    fn read(hash: &str) -> io::Result<RefObject> {
+        let (kind, contents) = read_object(hash)?;
+        let text = String::from_utf8_lossy(&contents);
+        Ok(RefObject {
+            hash: hash.to_string(),
+            commit: (kind == "commit")
+                .then(|| Commit::parse(&text))
+                .transpose()?,
+            tag: (kind == "tag").then(|| Tag::parse(&text)).transpose()?,
+            kind,
+        })
+    }
+
+    fn message(&self) -> &str {
+        match (&self.commit, &self.tag) {
+            (Some(commit), _) => &commit.message,
+            (_, Some(tag)) => &tag.message,
+            _ => "",
+        }
+    }
+
+    fn identity(&self, who: &str) -> Option<&Identity> {
+        match who {
+            "author" => self.commit.as_ref().map(|c| &c.author),
+            "committer" => self.commit.as_ref().map(|c| &c.committer),
+            "tagger" => self.tag.as_ref().and_then(|t| t.tagger.as_ref()),
+            // Commits are created by their committer, tags by their tagger
+            _ => self
+                .commit
+                .as_ref()
+                .map(|c| &c.committer)
+                .or_else(|| self.tag.as_ref().and_then(|t| t.tagger.as_ref())),
+        }
+    }
+}
+
+struct RefEntry {
+    refname: String,
+    object: RefObject,
+    // Object an annotated tag points to, for "%(*<field>)"
+    peeled: Option<RefObject>,
+}
+
+// Value of a field used for sorting: dates compare as timestamps
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(i64),
+    Text(String),
+}
+
+pub fn for_each_ref(options: &ForEachRefOptions) -> io::Result<()> {
+    let globs = options
+        .patterns
+        .iter()
+        .filter(|p| p.contains(['*', '?', '[']))
+        .map(|p| glob_regex(p))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut entries = vec![];
+    for (refname, hash) in list_refs("refs/")? {
+        let selected = options.patterns.is_empty()
+            || globs.iter().any(|glob| glob.is_match(&refname))
+            || options
+                .patterns
+                .iter()
+                .any(|pattern| prefix_matches(&refname, pattern));
+        if !selected {
+            continue;
+        }
+        let object = RefObject::read(&hash)?;
+        let peeled = match &object.tag {
+            Some(tag) => Some(RefObject::read(&tag.object)?),
+            None => None,
+        };
+        entries.push(RefEntry {
+            refname,
+            object,
+            peeled,
+        });
+    }
+
+    let head = Head::read()?;
+    let sort_keys = if options.sort.is_empty() {
+        vec!["refname".to_string()]
+    } else {
+        options.sort.clone()
+    };
+    // Stable sorts from the first key to the last leave the last key as the primary one
+    for key in &sort_keys {
+        let (descending, field) = match key.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, key.as_str()),
+        };
+        let mut keyed = entries
+            .into_iter()
+            .map(|entry| Ok((sort_value(&entry, field, &head)?, entry)))
+            .collect::<io::Result<Vec<_>>>()?;
+        keyed.sort_by(|(a, _), (b, _)| {
+            let order = a.cmp(b);
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        entries = keyed.into_iter().map(|(_, entry)| entry).collect();
+    }
+
+    let format = options.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+    let mut output = String::new();
+    for entry in entries.iter().take(options.count.unwrap_or(usize::MAX)) {
+        output.push_str(&format_ref(format, entry, &head)?);
+        output.push('\n');
+    }
+    print!("{}", output);
+    Ok(())
+}
+
+// A pattern without wildcards matches the references below it, e.g. "refs/heads" or "refs/heads/"
+fn prefix_matches(refname: &str, pattern: &str) -> bool {
+    refname
+        .strip_prefix(pattern)
+        .is_some_and(|rest| rest.is_empty() || pattern.ends_with('/') || rest.starts_with('/'))
+}
+
+fn format_ref(format: &str, entry: &RefEntry, head: &Head) -> io::Result<String> {
+    let mut output = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('%') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("%%") {
+            output.push('%');
+            rest = after;
+        } else if let Some(field) = rest.strip_prefix("%(") {
+            let end = field.find(')').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Malformed format string {}", rest),
+                )
+            })?;
+            output.push_str(&field_value(entry, &field[..end], head)?);
+            rest = &field[end + 1..];
+        } else if let Some(byte) = rest
+            .get(1..3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            output.push(byte as char);
+            rest = &rest[3..];
+        } else {
+            output.push('%');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn field_value(entry: &RefEntry, field: &str, head: &Head) -> io::Result<String> {
+    let (name, modifier) = match field.split_once(':') {
+        Some((name, modifier)) => (name, Some(modifier)),
+        None => (field, None),
+    };
+
+    // "%(*<field>)" is empty for references that are not annotated tags
+    let object = match name.strip_prefix('*') {
+        Some(_) => match &entry.peeled {
+            Some(peeled) => peeled,
+            None => return Ok(String::new()),
+        },
+        None => &entry.object,
+    };
+    let name = name.trim_start_matches('*');
+
+    let value = match (name, modifier) {
+        ("refname", None) => entry.refname.clone(),
+        ("refname", Some("short")) => shorten_ref_name(&entry.refname).to_string(),
+        ("refname", Some(modifier)) if modifier.starts_with("lstrip=") => {
+            let n: usize = modifier["lstrip=".len()..]
+                .parse()
+                .map_err(|_| unknown_field(field))?;
+            entry
+                .refname
+                .split('/')
+                .skip(n)
+                .collect::<Vec<_>>()
+                .join("/")
+        }
+        ("symref", _) => match read_raw_ref(&entry.refname)? {
+            Some(RefValue::Symbolic(target)) if modifier == Some("short") => {
+                shorten_ref_name(&target).to_string()
+            }
+            Some(RefValue::Symbolic(target)) => target,
+            _ => String::new(),
+        },
+        ("HEAD", None) => {
+            if head.refname() == Some(entry.refname.as_str()) {
+                "*".to_string()
+            } else {
+                " ".to_string()
+            }
+        }
+        ("objectname", None) => object.hash.clone(),
+        ("objectname", Some("short")) => object.hash[..7].to_string(),
+        ("objecttype", None) => object.kind.clone(),
+        ("upstream", _) => {
+            let upstream = match entry.refname.strip_prefix("refs/heads/") {
+                Some(branch) => branch_upstream(branch)?,
+                None => None,
+            };
+            match (upstream, modifier) {
+                (Some(upstream), Some("short")) => shorten_ref_name(&upstream).to_string(),
+                (Some(upstream), _) => upstream,
+                (None, _) => String::new(),
+            }
+        }
+        ("subject", None) => object.message().lines().next().unwrap_or("").to_string(),
+        ("body", None) => match object.message().split_once('\n') {
+            Some((_, body)) => body.trim_start_matches('\n').to_string(),
+            None => String::new(),
+        },
+        ("contents", None) => object.message().to_string(),
+        (name, modifier) => {
+            let Some((who, part)) = ["author", "committer", "tagger", "creator"]
+                .iter()
+                .find_map(|who| Some((*who, name.strip_prefix(who)?)))
+            else {
+                return Err(unknown_field(field));
+            };
+            let Some(identity) = object.identity(who) else {
+                return Ok(String::new());
+            };
+            match (part, modifier) {
+                ("name", None) => identity.name.clone(),
+                ("email", None) => format!("<{}>", identity.email),
+                ("date", mode) => {
+                    let mode = match mode {
+                        Some(mode) => DateMode::parse(mode)?,
+                        None => DateMode::Default,
+                    };
+                    format_date(identity, mode)
+                }
+                _ => return Err(unknown_field(field)),
+            }
+        }
+    };
+    Ok(value)
+}
+
+fn sort_value(entry: &RefEntry, field: &str, head: &Head) -> io::Result<SortValue> {
+    let name = field.trim_start_matches('*');
+    if let Some(who) = name.strip_suffix("date") {
+        let object = if field.starts_with('*') {
+            entry.peeled.as_ref()
+        } else {
+            Some(&entry.object)
+        };
+        let timestamp = object
+            .and_then(|object| object.identity(who))
+            .map(|identity| identity.timestamp)
+            .unwrap_or(0);
+        return Ok(SortValue::Number(timestamp));
+    }
+    Ok(SortValue::Text(field_value(entry, field, head)?))
+}
+
+fn unknown_field(field: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown field name: {}", field),
+    )
+}
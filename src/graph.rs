@@ -0,0 +1,123 @@
+// ASCII commit graph for `log --graph`
+//
+// Every lane holds the commit expected next in that column. Printing a commit turns its lane
+// into the lanes of its parents, then lanes waiting for the same commit are merged together.
+// Moving lanes are drawn with '/' and '\' one column per row.
+
+#[derive(Debug, Default)]
+pub struct Graph {
+    lanes: Vec<String>,
+}
+
+// Rows drawn for one commit
+pub struct GraphRows {
+    // Prefix of the line showing the commit
+    pub commit_row: String,
+    // Rows connecting the commit to its parents and merging lanes
+    pub transition_rows: Vec<String>,
+    // Prefix of every other line until the next commit
+    pub padding_row: String,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    pub fn next_commit(&mut self, hash: &str, parents: &[String]) -> GraphRows {
+        let column = match self.lanes.iter().position(|lane| lane == hash) {
+            Some(column) => column,
+            None => {
+                self.lanes.push(hash.to_string());
+                self.lanes.len() - 1
+            }
+        };
+
+        let commit_row = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(i, _)| if i == column { "*" } else { "|" })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Lanes after the commit, with its lane replaced by its parents: (from column, commit)
+        let mut expanded: Vec<(usize, String)> = vec![];
+        for (i, lane) in self.lanes.iter().enumerate() {
+            if i == column {
+                expanded.extend(parents.iter().map(|p| (i, p.clone())));
+            } else if lane != hash {
+                expanded.push((i, lane.clone()));
+            }
+        }
+
+        // Lanes waiting for the same commit are merged into the leftmost one
+        let mut next_lanes: Vec<String> = vec![];
+        for (_, lane) in &expanded {
+            if !next_lanes.contains(lane) {
+                next_lanes.push(lane.clone());
+            }
+        }
+
+        // Edges from the current columns to the target columns
+        let mut edges: Vec<(usize, usize)> = expanded
+            .iter()
+            .map(|(from, lane)| (*from, next_lanes.iter().position(|l| l == lane).unwrap()))
+            .collect();
+
+        let width = self.lanes.len().max(next_lanes.len()) * 2 - 1;
+        let mut transition_rows = vec![];
+        while edges.iter().any(|(from, to)| from != to) {
+            let mut row = vec![' '; width + 1];
+            for (from, to) in edges.iter_mut() {
+                if *to < *from {
+                    row[*from * 2 - 1] = '/';
+                    *from -= 1;
+                } else if *to > *from {
+                    row[*from * 2 + 1] = '\\';
+                    *from += 1;
+                } else if row[*from * 2] == ' ' {
+                    row[*from * 2] = '|';
+                }
+            }
+            transition_rows.push(row.iter().collect::<String>().trim_end().to_string());
+        }
+
+        self.lanes = next_lanes;
+        let padding_row = vec!["|"; self.lanes.len()].join(" ");
+
+        GraphRows {
+            commit_row,
+            transition_rows,
+            padding_row,
+        }
+    }
+}
+
+// Prefix every line of a formatted commit with the graph rows
+pub fn draw_commit(rows: &GraphRows, text: &str) -> String {
+    let width = std::iter::once(&rows.commit_row)
+        .chain(rows.transition_rows.iter())
+        .chain(std::iter::once(&rows.padding_row))
+        .map(|r| r.len())
+        .max()
+        .unwrap_or(0);
+    let pad = |row: &str, line: &str| {
+        if line.is_empty() {
+            row.to_string()
+        } else {
+            format!("{:<width$} {}", row, line, width = width)
+        }
+    };
+
+    let mut lines = text.lines();
+    let mut output = vec![pad(&rows.commit_row, lines.next().unwrap_or(""))];
+    for row in &rows.transition_rows {
+        output.push(pad(row, lines.next().unwrap_or("")));
+    }
+    for line in lines {
+        output.push(pad(&rows.padding_row, line));
+    }
+
+    output.join("\n")
+}
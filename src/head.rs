@@ -0,0 +1,129 @@
+// HEAD: the current branch, or a commit checked out without a branch (detached HEAD)
+
+use std::io;
+
+use crate::{
+    hooks::NULL_HASH,
+    object::peel_to_commit,
+    refs::{
+        list_refs, read_raw_ref, read_ref, resolve_symbolic, write_ref, write_symbolic_ref,
+        RefValue,
+    },
+    revwalk::{walk_commits, WalkOptions},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Head {
+    // "ref: refs/heads/<branch>", the branch has no commits yet until its ref file exists
+    Symbolic(String),
+    // Commit hash stored in HEAD itself
+    Detached(String),
+}
+
+impl Head {
+    pub fn read() -> io::Result<Head> {
+        match read_raw_ref("HEAD")? {
+            // The branch at the end of a chain of symbolic references is the current one
+            Some(RefValue::Symbolic(_)) => Ok(Head::Symbolic(resolve_symbolic("HEAD")?)),
+            Some(RefValue::Direct(commit)) => Ok(Head::Detached(commit)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Not an rgit repository (.rgit/HEAD not found)",
+            )),
+        }
+    }
+
+    // Point HEAD at the branch or commit, recording the move in the HEAD log
+    pub fn write(&self, message: &str) -> io::Result<()> {
+        match self {
+            Head::Symbolic(refname) => write_symbolic_ref("HEAD", refname, Some(message)),
+            Head::Detached(commit) => write_ref("HEAD", commit, None, false, message),
+        }
+    }
+
+    // Full name of the current branch, e.g. "refs/heads/master"
+    pub fn refname(&self) -> Option<&str> {
+        match self {
+            Head::Symbolic(refname) => Some(refname),
+            Head::Detached(_) => None,
+        }
+    }
+
+    // Short name of the current branch, e.g. "master"
+    pub fn branch(&self) -> Option<&str> {
+        self.refname()
+            .map(|refname| refname.strip_prefix("refs/heads/").unwrap_or(refname))
+    }
+
+    pub fn is_detached(&self) -> bool {
+        matches!(self, Head::Detached(_))
+    }
+
+    // Commit HEAD points to, None on a branch without commits
+    pub fn commit(&self) -> io::Result<Option<String>> {
+        match self {
+            Head::Symbolic(refname) => read_ref(refname),
+            Head::Detached(commit) => Ok(Some(commit.clone())),
+        }
+    }
+
+    // Move HEAD forward to a new commit: the current branch is updated,
+    // or HEAD itself when it is detached. Fails if HEAD moved since it was read.
+    pub fn advance(&self, commit: &str, message: &str) -> io::Result<()> {
+        let old = self.commit()?.unwrap_or(NULL_HASH.to_string());
+        match self {
+            Head::Symbolic(refname) => write_ref(refname, commit, Some(&old), true, message),
+            Head::Detached(_) => write_ref("HEAD", commit, Some(&old), false, message),
+        }
+    }
+
+    // "On branch master" or "HEAD detached at abc1234"
+    pub fn describe(&self) -> String {
+        match self {
+            Head::Symbolic(_) => format!("On branch {}", self.branch().unwrap_or_default()),
+            Head::Detached(commit) => {
+                format!("HEAD detached at {}", &commit[..commit.len().min(7)])
+            }
+        }
+    }
+}
+
+// Warn about the commits of a detached HEAD that no reference keeps once HEAD moves to `new`
+pub fn warn_orphaned(old: &str, new: &str) -> io::Result<()> {
+    let mut kept = vec![new.to_string()];
+    for (_, hash) in list_refs("refs/")? {
+        // Tags may point to trees or blobs, which keep no commits
+        if let Ok(commit) = peel_to_commit(&hash) {
+            kept.push(commit);
+        }
+    }
+
+    let orphaned = walk_commits(
+        &[old.to_string()],
+        &WalkOptions {
+            exclude: kept,
+            ..Default::default()
+        },
+    )?;
+    if orphaned.is_empty() {
+        return Ok(());
+    }
+
+    let count = orphaned.len();
+    eprintln!(
+        "Warning: you are leaving {} commit{} behind, not connected to\nany of your branches:\n",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    for (hash, commit) in orphaned.iter().take(5) {
+        eprintln!("  {} {}", &hash[..7], commit.subject());
+    }
+    if count > 5 {
+        eprintln!(" ... and {} more.", count - 5);
+    }
+    eprintln!(
+        "\nIf you want to keep them by creating a new branch, this may be a good time\nto do so with:\n\n rgit branch <new-branch-name> {}\n",
+        &old[..7]
+    );
+    Ok(())
+}
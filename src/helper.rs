@@ -1,7 +1,7 @@
 // All helper and reusable functions are stored
 
 use std::{
-    fs::{self, File, OpenOptions},
+    fs::File,
     io::{self, BufRead, BufReader, Read, Write},
     path::Path,
 };
@@ -10,6 +10,8 @@ use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use sha1::{Digest, Sha1};
 
+use crate::lockfile::write_locked;
+
 pub fn hash_and_store_obj(content_type: &str, content: &str) -> io::Result<String> {
     // Header information
     let header = format!("{} {}\0", content_type, content.len());
@@ -29,23 +31,25 @@ pub fn hash_and_store_obj(content_type: &str, content: &str) -> io::Result<Strin
     let compressed_obj = encoder.finish()?; // Get the compressed bytes
 
     // Create object file and folder
-    let object_dir = format!(".rgit/objects/{}", &hash_value_str[0..2]);
-    let object_path = format!("{}/{}", object_dir, &hash_value_str[2..]);
-
-    fs::create_dir_all(&object_dir)?; // Make sure to create object dir
-
-    // Create and write object file
-    let mut object_file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(&object_path)?;
-
-    object_file.write_all(&compressed_obj)?;
+    let object_path = format!(
+        ".rgit/objects/{}/{}",
+        &hash_value_str[0..2],
+        &hash_value_str[2..]
+    );
+    write_object_file(&object_path, &compressed_obj)?;
 
     Ok(hash_value_str)
 }
 
+// Store a compressed object. Objects never change once written, so an existing one is
+// kept; a new one appears through a lock file and rename, never half-written.
+pub fn write_object_file(object_path: &str, compressed: &[u8]) -> io::Result<()> {
+    if Path::new(object_path).is_file() {
+        return Ok(());
+    }
+    write_locked(object_path, compressed)
+}
+
 pub fn create_tree() -> io::Result<String> {
     let index_path = ".rgit/index";
     let mut tree_contents = vec![];
@@ -72,48 +76,6 @@ pub fn create_tree() -> io::Result<String> {
     Ok(tree_hash)
 }
 
-pub fn get_current_ref_branch() -> io::Result<Option<String>> {
-    let head_path = ".rgit/HEAD";
-    // Check if HEAD file exists
-    if Path::new(head_path).exists() {
-        // Read the current branch from HEAD file
-        let mut head_file = File::open(head_path)?;
-        let mut head_file_content = String::new();
-        head_file.read_to_string(&mut head_file_content)?;
-        // Extract the branch reference (assuming it's the second part)
-        let parts: Vec<&str> = head_file_content.split_whitespace().collect();
-
-        if parts.len() < 2 {
-            return Ok(None); // Invalid format in HEAD file
-        }
-
-        let branch_ref_file = parts[1].to_string();
-        Ok(Some(branch_ref_file))
-    } else {
-        Ok(None)
-    }
-}
-
-pub fn get_parent_commit() -> io::Result<Option<String>> {
-    if let Some(branch_ref_file) = get_current_ref_branch()? {
-        let branch_ref_path = format!(".rgit/{}", branch_ref_file);
-
-        // If there is not commit yet! return with None
-        if !Path::new(&branch_ref_path).exists() {
-            return Ok(None);
-        }
-
-        // Read the commit hash from the branch reference file
-        let mut ref_file = File::open(branch_ref_path)?;
-        let mut previous_commit = String::new();
-        ref_file.read_to_string(&mut previous_commit)?;
-
-        Ok(Some(previous_commit.trim().to_string()))
-    } else {
-        Ok(None)
-    }
-}
-
 // Helper function to compute file hashing (SHA-1)
 pub fn compute_file_hash(file_path: &Path) -> io::Result<String> {
     let mut file = File::open(file_path)?;
@@ -0,0 +1,76 @@
+// Client-side hooks executed from .rgit/hooks (or `core.hooksPath`)
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::config::get_config;
+
+// Hash used in hook arguments when there is no commit yet
+pub const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+
+fn hooks_dir() -> io::Result<PathBuf> {
+    match get_config("[core]", "hooksPath")? {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(PathBuf::from(".rgit/hooks")),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+// Run the hook if it exists and is executable, a non-zero exit status is returned as an error
+// Missing or non-executable hooks are skipped, like Git does
+pub fn run_hook(name: &str, args: &[&str], env: &[(&str, String)]) -> io::Result<()> {
+    let hook_path = hooks_dir()?.join(name);
+    if !is_executable(&hook_path) {
+        return Ok(());
+    }
+
+    let status = Command::new(&hook_path)
+        .args(args)
+        .env("GIT_DIR", ".rgit")
+        .env("GIT_INDEX_FILE", ".rgit/index")
+        .env("GIT_EDITOR", ":")
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .status()
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Cannot run {} hook '{}': {}", name, hook_path.display(), e),
+            )
+        })?;
+
+    if !status.success() {
+        let code = status
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "signal".to_string());
+        return Err(io::Error::other(format!(
+            "{} hook exited with status {}, aborting",
+            name, code
+        )));
+    }
+
+    Ok(())
+}
+
+// Run a hook whose exit status does not affect the outcome of the command (e.g. post-commit)
+pub fn run_notify_hook(name: &str, args: &[&str], env: &[(&str, String)]) {
+    if let Err(e) = run_hook(name, args, env) {
+        eprintln!("warning: {}", e);
+    }
+}
@@ -2,10 +2,12 @@
 
 use std::{
     fs::{self, File},
-    io::{self, Write},
+    io,
     path::Path,
 };
 
+use crate::refs::write_symbolic_ref;
+
 /// Create an empty .rgit repository or reinitialize an existing one
 pub fn init() -> io::Result<()> {
     // Check if the .rgit directory already exists
@@ -23,8 +25,7 @@ pub fn init() -> io::Result<()> {
 
     File::create(".rgit/config")?;
     File::create(".rgit/index")?;
-    let mut head_file = File::create(".rgit/HEAD")?;
-    head_file.write_all(b"ref: refs/heads/master")?;
+    write_symbolic_ref("HEAD", "refs/heads/master", None)?;
     println!("Initialized empty rgit repository.");
     Ok(())
 }
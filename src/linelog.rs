@@ -0,0 +1,369 @@
+// Line range history of `log -L`: follow ranges of lines back through the diffs of each commit
+
+use std::{collections::HashMap, io};
+
+use regex::Regex;
+
+use crate::{
+    diff::{diff_lines, format_hunk, read_blob_text, split_lines, Edit},
+    object::{read_tree, Commit},
+    revwalk::{select_commits, walk_commits, WalkOptions},
+};
+
+// Start or end of a line range
+#[derive(Debug, Clone)]
+pub enum RangeBound {
+    // 1-based line number
+    Line(usize),
+    // Number of lines from the start ("+N" or "-N"), only valid as the end of a range
+    Offset(isize),
+    // First line matching the regex
+    Pattern(Regex),
+}
+
+// Line range given on the command line: "<start>,<end>:<file>" or ":<funcname>:<file>"
+#[derive(Debug, Clone)]
+pub enum LineRangeSpec {
+    Lines {
+        start: RangeBound,
+        end: Option<RangeBound>,
+        path: String,
+    },
+    Function {
+        name: Regex,
+        path: String,
+    },
+}
+
+fn invalid_range(spec: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid line range '{}'", spec),
+    )
+}
+
+fn parse_bound(value: &str, spec: &str) -> io::Result<RangeBound> {
+    if let Some(pattern) = value.strip_prefix('/').and_then(|v| v.strip_suffix('/')) {
+        return Regex::new(pattern)
+            .map(RangeBound::Pattern)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()));
+    }
+    if value.starts_with('+') || value.starts_with('-') {
+        return value
+            .parse::<isize>()
+            .map(RangeBound::Offset)
+            .map_err(|_| invalid_range(spec));
+    }
+    match value.parse::<usize>() {
+        Ok(line) if line > 0 => Ok(RangeBound::Line(line)),
+        _ => Err(invalid_range(spec)),
+    }
+}
+
+impl LineRangeSpec {
+    pub fn parse(spec: &str) -> io::Result<LineRangeSpec> {
+        if let Some(rest) = spec.strip_prefix(':') {
+            let (name, path) = rest.split_once(':').ok_or_else(|| invalid_range(spec))?;
+            let name = Regex::new(name)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+            return Ok(LineRangeSpec::Function {
+                name,
+                path: path.to_string(),
+            });
+        }
+
+        let (range, path) = spec.rsplit_once(':').ok_or_else(|| invalid_range(spec))?;
+        let (start, end) = match range.split_once(',') {
+            Some((start, end)) => (start, Some(end).filter(|e| !e.is_empty())),
+            None => (range, None),
+        };
+        let start = parse_bound(start, spec)?;
+        if matches!(start, RangeBound::Offset(_)) {
+            return Err(invalid_range(spec));
+        }
+
+        Ok(LineRangeSpec::Lines {
+            start,
+            end: end.map(|e| parse_bound(e, spec)).transpose()?,
+            path: path.to_string(),
+        })
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            LineRangeSpec::Lines { path, .. } | LineRangeSpec::Function { path, .. } => path,
+        }
+    }
+
+    // Lines of the file covered by the range, as 0-based [start, end)
+    pub fn resolve(&self, lines: &[&str]) -> io::Result<(usize, usize)> {
+        let find = |pattern: &Regex, from: usize| {
+            (from..lines.len())
+                .find(|&i| pattern.is_match(lines[i]))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("No line matching '{}' in {}", pattern, self.path()),
+                    )
+                })
+        };
+
+        let (start, end) = match self {
+            LineRangeSpec::Function { name, .. } => {
+                // The function starts at a definition line matching the name and ends
+                // before the next definition
+                let start = (0..lines.len())
+                    .find(|&i| starts_definition(lines[i]) && name.is_match(lines[i]))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("No function matching '{}' in {}", name, self.path()),
+                        )
+                    })?;
+                let end = (start + 1..lines.len())
+                    .find(|&i| starts_definition(lines[i]))
+                    .unwrap_or(lines.len());
+                (start, end)
+            }
+            LineRangeSpec::Lines { start, end, .. } => {
+                let start = match start {
+                    RangeBound::Line(line) => line - 1,
+                    RangeBound::Pattern(pattern) => find(pattern, 0)?,
+                    RangeBound::Offset(_) => unreachable!("rejected when parsing"),
+                };
+                match end {
+                    None => (start, lines.len()),
+                    Some(RangeBound::Line(line)) if *line - 1 < start => (line - 1, start + 1),
+                    Some(RangeBound::Line(line)) => (start, *line),
+                    Some(RangeBound::Offset(offset)) if *offset < 0 => {
+                        (start.saturating_sub(offset.unsigned_abs() - 1), start + 1)
+                    }
+                    Some(RangeBound::Offset(offset)) => (start, start + (*offset as usize).max(1)),
+                    Some(RangeBound::Pattern(pattern)) => (start, find(pattern, start + 1)? + 1),
+                }
+            }
+        };
+
+        if start >= lines.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("File {} has only {} lines", self.path(), lines.len()),
+            ));
+        }
+        Ok((start, end.min(lines.len())))
+    }
+}
+
+// Lines starting a new definition, like Git's default funcname pattern
+fn starts_definition(line: &str) -> bool {
+    line.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+}
+
+// Ranges of lines tracked in every file, sorted and merged
+type FileRanges = HashMap<String, Vec<(usize, usize)>>;
+
+fn add_ranges(ranges: &mut FileRanges, path: &str, new_ranges: &[(usize, usize)]) {
+    let file_ranges = ranges.entry(path.to_string()).or_default();
+    file_ranges.extend(new_ranges.iter().filter(|(start, end)| start < end));
+    file_ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for &(start, end) in file_ranges.iter() {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    *file_ranges = merged;
+}
+
+// Map a range of new lines back to the old lines through the edits
+// Returns the old range, the edits covering the range and whether the range was changed
+fn map_range(
+    edits: &[Edit],
+    (start, end): (usize, usize),
+) -> ((usize, usize), (usize, usize), bool) {
+    let produces = |edit: &Edit, line: usize| match edit {
+        Edit::Equal(_, y) | Edit::Insert(y) => *y == line,
+        Edit::Delete(_) => false,
+    };
+    let mut first = edits
+        .iter()
+        .position(|e| produces(e, start))
+        .unwrap_or(edits.len());
+    let mut last = edits
+        .iter()
+        .position(|e| produces(e, end - 1))
+        .map_or(edits.len(), |i| i + 1);
+
+    // Lines removed next to changed lines at the edges belong to the range
+    if matches!(edits.get(first), Some(Edit::Insert(_))) {
+        while first > 0 && matches!(edits[first - 1], Edit::Delete(_)) {
+            first -= 1;
+        }
+    }
+    if last > 0 && matches!(edits[last - 1], Edit::Insert(_)) {
+        while matches!(edits.get(last), Some(Edit::Delete(_))) {
+            last += 1;
+        }
+    }
+
+    let old_lines_before = |i: usize| {
+        edits[..i]
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count()
+    };
+    let changed = edits[first..last]
+        .iter()
+        .any(|e| !matches!(e, Edit::Equal(..)));
+    (
+        (old_lines_before(first), old_lines_before(last)),
+        (first, last),
+        changed,
+    )
+}
+
+// Commits changing the line ranges, and the hunks of the ranges by commit hash
+type LineHistory = (Vec<(String, Commit)>, HashMap<String, String>);
+
+// Walk the history and keep the commits changing the line ranges, with the hunks of the ranges
+pub fn line_log(
+    start_commit: &str,
+    specs: &[LineRangeSpec],
+    options: &WalkOptions,
+) -> io::Result<LineHistory> {
+    // Children have to be visited before their parents to hand the ranges down
+    let walk = WalkOptions {
+        first_parent: options.first_parent,
+        topo_order: true,
+        ..WalkOptions::default()
+    };
+    let commits = walk_commits(&[start_commit.to_string()], &walk)?;
+
+    let mut pending: HashMap<String, FileRanges> = HashMap::new();
+    if let Some((_, tip)) = commits.first() {
+        let tree = read_tree(&tip.tree)?;
+        let mut ranges = FileRanges::new();
+        for spec in specs {
+            let blob = tree.get(spec.path()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("There is no path {} in the commit", spec.path()),
+                )
+            })?;
+            let text = read_blob_text(Some(blob))?;
+            add_ranges(
+                &mut ranges,
+                spec.path(),
+                &[spec.resolve(&split_lines(&text))?],
+            );
+        }
+        pending.insert(start_commit.to_string(), ranges);
+    }
+
+    let mut shown = vec![];
+    let mut patches = HashMap::new();
+    for (hash, commit) in commits {
+        let Some(mut ranges) = pending.remove(&hash) else {
+            continue;
+        };
+        ranges.retain(|_, r| !r.is_empty());
+        if ranges.is_empty() {
+            continue;
+        }
+
+        let tree = read_tree(&commit.tree)?;
+        let parents: Vec<String> = if options.first_parent {
+            commit.parents.iter().take(1).cloned().collect()
+        } else {
+            commit.parents.clone()
+        };
+        let parent_trees = parents
+            .iter()
+            .map(|p| read_tree(&Commit::read(p)?.tree))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut patch = String::new();
+        let mut paths: Vec<&String> = ranges.keys().collect();
+        paths.sort();
+        for path in paths {
+            let file_ranges = &ranges[path];
+            let blob = tree.get(path);
+
+            // A parent with the same file takes over the ranges unchanged
+            if let Some(i) = parent_trees.iter().position(|t| t.get(path) == blob) {
+                let parent_ranges = pending.entry(parents[i].clone()).or_default();
+                add_ranges(parent_ranges, path, file_ranges);
+                continue;
+            }
+
+            let new_text = read_blob_text(blob.map(|b| b.as_str()))?;
+            let new_lines = split_lines(&new_text);
+            for (i, parent_tree) in parent_trees.iter().enumerate().rev() {
+                let old_blob = parent_tree.get(path);
+                let old_text = read_blob_text(old_blob.map(|b| b.as_str()))?;
+                let old_lines = split_lines(&old_text);
+                let edits = diff_lines(&old_lines, &new_lines);
+
+                let mut parent_ranges = vec![];
+                let mut hunks = String::new();
+                for &range in file_ranges {
+                    let (old_range, (first, last), changed) = map_range(&edits, range);
+                    parent_ranges.push(old_range);
+                    if changed {
+                        hunks.push_str(&format_hunk(
+                            &old_lines,
+                            &new_lines,
+                            &edits[first..last],
+                            old_range.0,
+                            range.0,
+                        ));
+                    }
+                }
+                add_ranges(
+                    pending.entry(parents[i].clone()).or_default(),
+                    path,
+                    &parent_ranges,
+                );
+
+                // The hunks are shown against the first parent
+                if i == 0 && !hunks.is_empty() {
+                    let old_name = match old_blob {
+                        Some(_) => format!("a/{}", path),
+                        None => "/dev/null".to_string(),
+                    };
+                    patch.push_str(&format!(
+                        "diff --git a/{0} b/{0}\n--- {1}\n+++ b/{0}\n{2}",
+                        path, old_name, hunks
+                    ));
+                }
+            }
+
+            // Every line of a root commit was added by it
+            if parent_trees.is_empty() {
+                let edits = diff_lines(&[], &new_lines);
+                patch.push_str(&format!(
+                    "diff --git a/{0} b/{0}\n--- /dev/null\n+++ b/{0}\n",
+                    path
+                ));
+                for &range in file_ranges {
+                    patch.push_str(&format_hunk(
+                        &[],
+                        &new_lines,
+                        &edits[range.0..range.1],
+                        0,
+                        range.0,
+                    ));
+                }
+            }
+        }
+
+        if !patch.is_empty() {
+            patches.insert(hash.clone(), patch);
+            shown.push((hash, commit));
+        }
+    }
+
+    Ok((select_commits(shown, options)?, patches))
+}
@@ -0,0 +1,97 @@
+// Git-style lock files: a file is changed by writing "<path>.lock" and renaming it over the
+// file, so readers see either the old or the new contents and a crash leaves the file intact.
+// Creating the lock fails while another command holds it.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+pub struct LockFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    file: Option<File>,
+    // Set once the lock file has been renamed over the file or released
+    done: bool,
+}
+
+impl LockFile {
+    // Take the lock of a file, failing if another command holds it
+    pub fn acquire(path: impl AsRef<Path>) -> io::Result<LockFile> {
+        let path = path.as_ref().to_path_buf();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!(
+                        "Unable to create '{}': File exists.\n\nAnother rgit process seems to be running in this repository.\nIf it is not the case, a previous command may have crashed:\nremove the file manually to continue.",
+                        lock_path.display()
+                    ),
+                ),
+                _ => e,
+            })?;
+
+        Ok(LockFile {
+            path,
+            lock_path,
+            file: Some(file),
+            done: false,
+        })
+    }
+
+    // The new contents go to the lock file until `commit`
+    pub fn write_all(&mut self, contents: &[u8]) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.write_all(contents),
+            None => Err(io::Error::other("Lock file already closed")),
+        }
+    }
+
+    // Replace the file with the written contents and release the lock
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        fs::rename(&self.lock_path, &self.path)?;
+        self.done = true;
+        Ok(())
+    }
+
+    // Remove the file itself while holding its lock, then release the lock
+    pub fn delete(self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+// A lock that is not committed is rolled back, leaving the file untouched
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        self.file.take();
+        if !self.done {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+// Replace the contents of a file atomically under its lock
+pub fn write_locked(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let mut lock = LockFile::acquire(path)?;
+    lock.write_all(contents)?;
+    lock.commit()
+}
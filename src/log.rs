@@ -1,71 +1,165 @@
-use std::{
-    fs::{self, File},
-    io::{self, Read},
-    path::Path,
+use std::{collections::HashMap, io};
+
+use crate::{
+    config::get_config,
+    diff::{commit_changes, format_file_patch},
+    graph::{draw_commit, Graph},
+    head::Head,
+    linelog::{line_log, LineRangeSpec},
+    mailmap::Mailmap,
+    object::{read_object_as, Commit},
+    pretty::{load_decorations, pretty_commit, DateMode, FormatContext, PrettyFormat},
+    revwalk::{parse_revision_args, path_matches, walk_commits, RevisionRange, WalkOptions},
+    signing::verify_object,
 };
 
-use flate2::bufread::ZlibDecoder;
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    // Revisions and ranges to list (e.g. "main", "A..B", "--not", "--all"), defaults to HEAD
+    pub revisions: Vec<String>,
+    // Built-in format or format string, defaults to "medium"
+    pub pretty: Option<PrettyFormat>,
+    // How dates are shown
+    pub date_mode: DateMode,
+    // Show abbreviated commit hashes
+    pub abbrev_commit: bool,
+    // Show ref names next to commits: None, Some(false) for short names, Some(true) for full names
+    pub decorate: Option<bool>,
+    // Draw the commit graph next to the log
+    pub graph: bool,
+    // Check the signature of signed commits
+    pub show_signature: bool,
+    // Show canonical identities from .mailmap, defaults to the `log.mailmap` config (true)
+    pub use_mailmap: Option<bool>,
+    // Show the changes of every commit as a patch
+    pub patch: bool,
+    // Show every change of the commits found by the pickaxe, not only the matching files
+    pub pickaxe_all: bool,
+    // Only show the history of these line ranges (`-L`)
+    pub line_ranges: Vec<LineRangeSpec>,
+    // Order and parents followed by the history walk
+    pub walk: WalkOptions,
+}
 
-pub fn log() -> io::Result<()> {
-    // TODO :: Need to check for current branch like (main or dev) or show all commit combine with all branches
-    // Default to master branch
-    let head_path = Path::new(".rgit/refs/heads/master");
+pub fn log(options: &LogOptions) -> io::Result<()> {
+    // Start from the given revisions, or from the commit HEAD points to (branch or detached)
+    let range = if options.revisions.is_empty() {
+        RevisionRange {
+            starts: Head::read()?.commit()?.into_iter().collect(),
+            ..RevisionRange::default()
+        }
+    } else {
+        parse_revision_args(&options.revisions)?
+    };
 
-    if !head_path.exists() {
+    if range.starts.is_empty() {
         println!("No commits found");
         return Ok(());
     }
-    // Get current commit hash value
-    let mut current_commit = fs::read_to_string(head_path)?.trim().to_string();
 
-    while !current_commit.is_empty() {
-        let mut parent_commit: Option<String> = None;
-        // Retrieve the commit object path
-        let commit_path = format!(
-            ".rgit/objects/{}/{}",
-            &current_commit[0..2],
-            &current_commit[2..]
-        );
+    let pretty = options.pretty.clone().unwrap_or(PrettyFormat::Medium);
+    let context = FormatContext {
+        date_mode: options.date_mode,
+        decorations: load_decorations(options.decorate == Some(true))?,
+        abbrev_commit: options.abbrev_commit,
+        decorate: options.decorate.is_some(),
+        mailmap: Mailmap::load()?,
+        use_mailmap: match options.use_mailmap {
+            Some(use_mailmap) => use_mailmap,
+            None => {
+                !get_config("[log]", "mailmap")?.is_some_and(|v| v.eq_ignore_ascii_case("false"))
+            }
+        },
+    };
+    // Multi-line built-in formats separate commits with a blank line
+    let separate_entries = !matches!(pretty, PrettyFormat::Oneline | PrettyFormat::Format(_));
 
-        // Read the commit file as raw bytes
-        let mut commit_object_file = File::open(&commit_path)?;
-        let mut encoded_commit_object = Vec::new(); // Use a byte vector
-        commit_object_file.read_to_end(&mut encoded_commit_object)?;
+    // The graph needs children before their parents
+    let mut walk = options.walk.clone();
+    walk.topo_order |= options.graph;
+    walk.exclude = range.exclude;
+    let mut graph = Graph::new();
+    let mut separator = String::new();
 
-        // Decompress the commit object using ZlibDecoder
-        let mut d = ZlibDecoder::new(&encoded_commit_object[..]);
-        let mut commit_contents = Vec::new(); // Use a byte vector for decompressed data
-        d.read_to_end(&mut commit_contents)?;
+    let (commits, line_patches) = if options.line_ranges.is_empty() {
+        (walk_commits(&range.starts, &walk)?, HashMap::new())
+    } else {
+        line_log(&range.starts[0], &options.line_ranges, &walk)?
+    };
 
-        // Convert the decompressed contents to a string (if valid UTF-8)
-        let commit_contents_str = String::from_utf8(commit_contents).map_err(|_| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Commit content is not valid UTF-8",
-            )
-        })?;
+    for (i, (commit_hash, commit)) in commits.into_iter().enumerate() {
+        if separate_entries && i > 0 {
+            println!("{}", separator);
+        }
 
-        println!("commit {}", current_commit);
-        for line in commit_contents_str.lines() {
-            if line.starts_with("Author:") || line.starts_with("Message:") || line.is_empty() {
-                println!("{}", line);
-            } else if line.contains("Parent:") {
-                // TODO :: Check and fix incorrect value for parent
-                parent_commit = line.split_whitespace().nth(2).map(String::from);
-            } else if line.starts_with("Tree:") {
-                // Skip the commit tree (commit tree indicate the files that contain blobs)
+        // Print the signature check result after the "commit <hash>" line
+        let signature_check = if options.show_signature {
+            match read_object_as(&commit_hash, "commit").and_then(|c| verify_object(&c)) {
+                Ok(check) => check.map(|c| c.describe()),
+                Err(e) => Some(format!("Cannot verify signature: {}", e)),
             }
+        } else {
+            None
+        };
+
+        let mut output = pretty_commit(&pretty, &commit_hash, &commit, &context)?;
+        if let Some(check) = signature_check {
+            output = if separate_entries {
+                let (first_line, rest) = output.split_once('\n').unwrap_or((&output, ""));
+                format!("{}\n{}\n{}", first_line, check, rest)
+            } else {
+                format!("{}\n{}", check, output)
+            };
         }
-        println!();
 
-        // If no parent commit exists, end the traversal (first commit case)
-        if parent_commit.is_none() {
-            break;
+        let patch = match line_patches.get(&commit_hash) {
+            Some(patch) => patch.clone(),
+            None if options.patch => commit_patch(&commit, options)?,
+            None => String::new(),
+        };
+        if !patch.is_empty() {
+            let separator = if separate_entries { "\n\n" } else { "\n" };
+            output = format!("{}{}{}", output, separator, patch.trim_end());
         }
 
-        // Move to the parent commit for the next iteration
-        current_commit = parent_commit.unwrap();
+        if options.graph {
+            let parents = if walk.first_parent {
+                &commit.parents[..commit.parents.len().min(1)]
+            } else {
+                &commit.parents[..]
+            };
+            let rows = graph.next_commit(&commit_hash, parents);
+            println!("{}", draw_commit(&rows, &output));
+            separator = rows.padding_row;
+        } else {
+            println!("{}", output);
+        }
     }
 
     Ok(())
 }
+
+// Patch of the changes of a commit, limited to the files matching the pickaxe and paths
+fn commit_patch(commit: &Commit, options: &LogOptions) -> io::Result<String> {
+    // Without --pickaxe-all only the files matching the pickaxe are shown
+    let pickaxe = options
+        .walk
+        .filter
+        .pickaxe
+        .as_ref()
+        .filter(|_| !options.pickaxe_all);
+    let mut patch = String::new();
+    for change in commit_changes(commit)? {
+        if !options.walk.paths.is_empty() && !path_matches(&change.path, &options.walk.paths) {
+            continue;
+        }
+        if let Some(pickaxe) = pickaxe {
+            if !pickaxe.matches_change(&change)? {
+                continue;
+            }
+        }
+        patch.push_str(&format_file_patch(&change)?);
+    }
+
+    Ok(patch)
+}
@@ -0,0 +1,228 @@
+// .mailmap: map the names and emails used in commits to canonical identities
+//
+// Supported lines, as in Git:
+//   Proper Name <commit@email>
+//   <proper@email> <commit@email>
+//   Proper Name <proper@email> <commit@email>
+//   Proper Name <proper@email> Commit Name <commit@email>
+
+use std::{
+    fs,
+    io::{self, BufRead},
+};
+
+use crate::config::get_config;
+
+#[derive(Debug, Clone)]
+struct MailmapEntry {
+    proper_name: Option<String>,
+    proper_email: Option<String>,
+    commit_name: Option<String>,
+    commit_email: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mailmap {
+    entries: Vec<MailmapEntry>,
+}
+
+// Split "Name <email>" pieces of a line: [(name, email), ...]
+fn parse_identities(line: &str) -> Vec<(Option<String>, String)> {
+    let mut identities = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|e| e + start) else {
+            break;
+        };
+        let name = rest[..start].trim();
+        identities.push((
+            (!name.is_empty()).then(|| name.to_string()),
+            rest[start + 1..end].trim().to_string(),
+        ));
+        rest = &rest[end + 1..];
+    }
+    identities
+}
+
+impl Mailmap {
+    pub fn parse(contents: &str) -> Mailmap {
+        let mut entries = vec![];
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let entry = match parse_identities(line).as_slice() {
+                [(name, email)] => MailmapEntry {
+                    proper_name: name.clone(),
+                    proper_email: None,
+                    commit_name: None,
+                    commit_email: email.clone(),
+                },
+                [(proper_name, proper_email), (commit_name, commit_email)] => MailmapEntry {
+                    proper_name: proper_name.clone(),
+                    proper_email: Some(proper_email.clone()),
+                    commit_name: commit_name.clone(),
+                    commit_email: commit_email.clone(),
+                },
+                _ => continue,
+            };
+            entries.push(entry);
+        }
+        Mailmap { entries }
+    }
+
+    // Load the .mailmap file of the working tree and the file set with `mailmap.file`,
+    // an empty map when there is none
+    pub fn load() -> io::Result<Mailmap> {
+        let mut contents = String::new();
+        let files = std::iter::once(".mailmap".to_string()).chain(get_config("[mailmap]", "file")?);
+        for file in files {
+            match fs::read_to_string(&file) {
+                Ok(file_contents) => {
+                    contents.push_str(&file_contents);
+                    contents.push('\n');
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Mailmap::parse(&contents))
+    }
+
+    // Canonical name and email, entries matching the name as well win over email-only entries
+    // Names and emails are compared case-insensitively
+    pub fn lookup(&self, name: &str, email: &str) -> (String, String) {
+        let matching = |with_name: bool| {
+            self.entries.iter().rev().filter(move |entry| {
+                entry.commit_email.eq_ignore_ascii_case(email)
+                    && match &entry.commit_name {
+                        Some(commit_name) => with_name && commit_name.eq_ignore_ascii_case(name),
+                        None => !with_name,
+                    }
+            })
+        };
+        let candidates: Vec<&MailmapEntry> = matching(true).chain(matching(false)).collect();
+
+        (
+            candidates
+                .iter()
+                .find_map(|entry| entry.proper_name.clone())
+                .unwrap_or_else(|| name.to_string()),
+            candidates
+                .iter()
+                .find_map(|entry| entry.proper_email.clone())
+                .unwrap_or_else(|| email.to_string()),
+        )
+    }
+}
+
+// `check-mailmap`: print the canonical "Name <email>" of each contact ("Name <email>" or "<email>")
+pub fn check_mailmap(contacts: &[String], stdin: bool) -> io::Result<()> {
+    let mailmap = Mailmap::load()?;
+    let check = |contact: &str| -> io::Result<()> {
+        let Some((name, email)) = parse_identities(contact.trim()).into_iter().next() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unable to parse contact: {}", contact),
+            ));
+        };
+        let name = name.unwrap_or_default();
+        match mailmap.lookup(&name, &email) {
+            (name, email) if name.is_empty() => println!("<{}>", email),
+            (name, email) => println!("{} <{}>", name, email),
+        }
+        Ok(())
+    };
+
+    for contact in contacts {
+        check(contact)?;
+    }
+    if stdin {
+        for line in io::stdin().lock().lines() {
+            check(&line?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str, email: &str) -> (String, String) {
+        (name.to_string(), email.to_string())
+    }
+
+    #[test]
+    fn parse_line_forms() {
+        let mailmap = Mailmap::parse(
+            "# comment\n\
+             Proper Name <commit@example.com>\n\
+             <proper@example.com> <old@example.com>\n\
+             Both <both@example.com> <any@example.com> # trailing comment\n\
+             Joe <joe@example.com> joe <JOE@example.com>\n\
+             not an entry\n",
+        );
+        assert_eq!(mailmap.entries.len(), 4);
+        assert_eq!(
+            mailmap.lookup("x", "commit@example.com"),
+            pair("Proper Name", "commit@example.com")
+        );
+        assert_eq!(
+            mailmap.lookup("Old", "old@example.com"),
+            pair("Old", "proper@example.com")
+        );
+        assert_eq!(
+            mailmap.lookup("Any", "any@example.com"),
+            pair("Both", "both@example.com")
+        );
+    }
+
+    #[test]
+    fn lookup_by_name_and_email() {
+        let mailmap = Mailmap::parse(
+            "Email Only <e@example.com>\n\
+             Joe <joe@example.com> joe <shared@example.com>\n\
+             Jane <jane@example.com> jane <shared@example.com>\n",
+        );
+        // Names and emails are compared case-insensitively
+        assert_eq!(
+            mailmap.lookup("JOE", "Shared@Example.com"),
+            pair("Joe", "joe@example.com")
+        );
+        assert_eq!(
+            mailmap.lookup("jane", "shared@example.com"),
+            pair("Jane", "jane@example.com")
+        );
+        // An entry with a commit name only applies to that name
+        assert_eq!(
+            mailmap.lookup("someone", "shared@example.com"),
+            pair("someone", "shared@example.com")
+        );
+        assert_eq!(
+            mailmap.lookup("E", "E@EXAMPLE.COM"),
+            pair("Email Only", "E@EXAMPLE.COM")
+        );
+        assert_eq!(
+            mailmap.lookup("Nobody", "nobody@example.com"),
+            pair("Nobody", "nobody@example.com")
+        );
+    }
+
+    #[test]
+    fn later_and_name_matching_entries_win() {
+        let mailmap = Mailmap::parse(
+            "First <a@example.com>\n\
+             Second <a@example.com>\n\
+             <proper@example.com> <a@example.com>\n\
+             Named <named@example.com> Al <a@example.com>\n",
+        );
+        assert_eq!(
+            mailmap.lookup("Al", "a@example.com"),
+            pair("Named", "named@example.com")
+        );
+        // The name and the email may come from different entries
+        assert_eq!(
+            mailmap.lookup("Bo", "a@example.com"),
+            pair("Second", "proper@example.com")
+        );
+    }
+}
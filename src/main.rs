@@ -1,26 +1,68 @@
 mod add;
+mod blame;
 mod branch;
 mod checkout;
 mod commit;
 mod config;
 mod diff;
+mod foreachref;
+mod graph;
+mod head;
 mod helper;
+mod hooks;
 mod init;
+mod linelog;
+mod lockfile;
 mod log;
+mod mailmap;
+mod object;
+mod pretty;
+mod reflog;
+mod refs;
+mod revlist;
+mod revparse;
+mod revwalk;
+mod shortlog;
+mod show;
+mod signing;
 mod status;
 mod tag;
+mod trailer;
+mod worktree;
 
 use add::add;
-use branch::{branch, delete_branch};
-use checkout::checkout;
-use clap::{Arg, Command};
-use commit::commit;
-use config::{add_remote, handle_config_command, remove_remote};
-use diff::diff;
+use blame::{blame, BlameOptions};
+use branch::{
+    branch, check_branch_name, delete_branch, list_branches, rename_branch, BranchListOptions,
+    BranchOptions,
+};
+use checkout::{checkout, restore, switch, CheckoutOptions, RestoreOptions, SwitchOptions};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use commit::{commit, verify_commit, CommitOptions};
+use config::{add_remote, handle_config_command, parse_config_key, remove_remote};
+use diff::{diff, Pickaxe};
+use foreachref::{for_each_ref, ForEachRefOptions};
 use init::init;
-use log::log;
+use linelog::LineRangeSpec;
+use log::{log, LogOptions};
+use mailmap::check_mailmap;
+use pretty::{parse_date, DateMode, PrettyFormat};
+use reflog::{reflog_delete, reflog_expire, reflog_show, ReflogExpireOptions};
+use refs::{
+    show_ref, symbolic_ref, update_ref, update_refs_from_stdin, ShowRefOptions, SymbolicRefOptions,
+    UpdateRefOptions,
+};
+use revlist::{rev_list, RevListOptions};
+use revparse::{check_ref_format, rev_parse, RevParseOptions};
+use revwalk::{compile_patterns, CommitFilter, WalkOptions};
+use shortlog::{shortlog, ShortlogGroup, ShortlogOptions};
+use show::{show, ShowOptions};
 use status::status;
-use tag::{delete_tag, list_tags, tag};
+use tag::{delete_tag, list_tags, tag, verify_tag, TagOptions};
+use trailer::{
+    interpret_trailers, InterpretTrailersOptions, TrailerIfExists, TrailerIfMissing,
+    TrailerOptions, TrailerWhere,
+};
 
 fn main() {
     // CLI interface
@@ -33,31 +75,625 @@ fn main() {
                 Command::new("init")
                     .about("Create an empty Git repository or reinitialize an existing one"),
             )
-            .subcommand(Command::new("log").about("Show commit log"))
+            .subcommand(
+                Command::new("log")
+                    .about("Show commit log")
+                    .args(revision_args("Revisions or ranges to list, e.g. main, A..B, A...B or ^A (default HEAD)"))
+                    .arg(
+                        Arg::new("format")
+                            .long("format")
+                            .help("Pretty-print commits with the given format string (e.g. '%h %an %s')"),
+                    )
+                    .arg(Arg::new("pretty").long("pretty").help(
+                        "Pretty-print commits: oneline, short, medium, full, fuller, raw or format:<string>",
+                    ))
+                    .arg(
+                        Arg::new("oneline")
+                            .long("oneline")
+                            .action(ArgAction::SetTrue)
+                            .help("Shorthand for --pretty=oneline --abbrev-commit"),
+                    )
+                    .arg(
+                        Arg::new("abbrev_commit")
+                            .long("abbrev-commit")
+                            .action(ArgAction::SetTrue)
+                            .help("Show abbreviated commit hashes"),
+                    )
+                    .arg(
+                        Arg::new("graph")
+                            .long("graph")
+                            .action(ArgAction::SetTrue)
+                            .help("Draw a text-based graph of the commit history"),
+                    )
+                    .arg(
+                        Arg::new("decorate")
+                            .long("decorate")
+                            .num_args(0..=1)
+                            .require_equals(true)
+                            .default_missing_value("short")
+                            .value_parser(["short", "full", "no"])
+                            .help("Show ref names of the commits (short, full or no)"),
+                    )
+                    .arg(Arg::new("date").long("date").help(
+                        "Date format: default, relative, local, iso, iso-strict, rfc, short, raw or unix",
+                    ))
+                    .arg(
+                        Arg::new("show_signature")
+                            .long("show-signature")
+                            .action(ArgAction::SetTrue)
+                            .help("Check the signature of signed commits"),
+                    )
+                    .arg(
+                        Arg::new("use_mailmap")
+                            .long("use-mailmap")
+                            .action(ArgAction::SetTrue)
+                            .overrides_with("no_use_mailmap")
+                            .help("Show canonical author and committer names from .mailmap"),
+                    )
+                    .arg(
+                        Arg::new("no_use_mailmap")
+                            .long("no-use-mailmap")
+                            .action(ArgAction::SetTrue)
+                            .overrides_with("use_mailmap")
+                            .help("Show author and committer names as recorded in the commits"),
+                    )
+                    .arg(
+                        Arg::new("first_parent")
+                            .long("first-parent")
+                            .action(ArgAction::SetTrue)
+                            .help("Follow only the first parent of merge commits"),
+                    )
+                    .arg(
+                        Arg::new("topo_order")
+                            .long("topo-order")
+                            .action(ArgAction::SetTrue)
+                            .help("Show no parents before all of their children"),
+                    )
+                    .arg(
+                        Arg::new("reverse")
+                            .long("reverse")
+                            .action(ArgAction::SetTrue)
+                            .help("Output the commits in reverse order"),
+                    )
+                    .arg(
+                        Arg::new("ancestry_path")
+                            .long("ancestry-path")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show descendants of the excluded commits"),
+                    )
+                    .arg(
+                        Arg::new("author")
+                            .long("author")
+                            .action(ArgAction::Append)
+                            .help("Show commits whose author matches the pattern"),
+                    )
+                    .arg(
+                        Arg::new("committer")
+                            .long("committer")
+                            .action(ArgAction::Append)
+                            .help("Show commits whose committer matches the pattern"),
+                    )
+                    .arg(
+                        Arg::new("grep")
+                            .long("grep")
+                            .action(ArgAction::Append)
+                            .help("Show commits whose message matches the pattern"),
+                    )
+                    .arg(
+                        Arg::new("regexp_ignore_case")
+                            .short('i')
+                            .long("regexp-ignore-case")
+                            .action(ArgAction::SetTrue)
+                            .help("Match the patterns case-insensitively"),
+                    )
+                    .arg(
+                        Arg::new("all_match")
+                            .long("all-match")
+                            .action(ArgAction::SetTrue)
+                            .help("Show commits whose message matches all --grep patterns"),
+                    )
+                    .arg(
+                        Arg::new("invert_grep")
+                            .long("invert-grep")
+                            .action(ArgAction::SetTrue)
+                            .help("Show commits whose message does not match --grep"),
+                    )
+                    .arg(
+                        Arg::new("since")
+                            .long("since")
+                            .visible_alias("after")
+                            .help("Show commits more recent than the date"),
+                    )
+                    .arg(
+                        Arg::new("until")
+                            .long("until")
+                            .visible_alias("before")
+                            .help("Show commits older than the date"),
+                    )
+                    .arg(
+                        Arg::new("max_count")
+                            .short('n')
+                            .long("max-count")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Limit the number of commits to output"),
+                    )
+                    .arg(
+                        Arg::new("skip")
+                            .long("skip")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Skip the given number of commits before output"),
+                    )
+                    .arg(
+                        Arg::new("pickaxe_string")
+                            .short('S')
+                            .value_name("string")
+                            .conflicts_with("pickaxe_regex")
+                            .help("Show commits changing the number of occurrences of the string"),
+                    )
+                    .arg(
+                        Arg::new("pickaxe_regex")
+                            .short('G')
+                            .value_name("regex")
+                            .help("Show commits whose added or removed lines match the regex"),
+                    )
+                    .arg(
+                        Arg::new("pickaxe_all")
+                            .long("pickaxe-all")
+                            .action(ArgAction::SetTrue)
+                            .help("Show all changes of the commits found by -S or -G"),
+                    )
+                    .arg(
+                        Arg::new("patch")
+                            .short('p')
+                            .long("patch")
+                            .action(ArgAction::SetTrue)
+                            .help("Show the patch of every commit"),
+                    )
+                    .arg(
+                        Arg::new("line_range")
+                            .short('L')
+                            .value_name("range")
+                            .action(ArgAction::Append)
+                            .help("Trace the history of a line range: <start>,<end>:<file> or :<funcname>:<file>"),
+                    )
+                    .arg(
+                        Arg::new("paths")
+                            .num_args(1..)
+                            .last(true)
+                            .help("Only show commits changing the given paths"),
+                    ),
+            )
             .subcommand(
                 Command::new("add")
                     .about("Add file contents to the index")
-                    .arg(Arg::new("file").required(true).help("The file to add")),
+                    .arg(Arg::new("file").required(true).help("The file to add"))
+                    .arg(
+                        Arg::new("no_verify")
+                            .long("no-verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Bypass the pre-add hook"),
+                    ),
             )
             .subcommand(
                 Command::new("checkout")
-                    .about("Checkout to commit and switch branch")
+                    .about("Checkout to commit and switch branch, or restore files")
+                    .arg(
+                        Arg::new("args")
+                            .num_args(0..)
+                            .help("Branch name or commit revision ('-' for the previous branch), optionally followed by paths"),
+                    )
+                    .arg(
+                        Arg::new("paths")
+                            .num_args(0..)
+                            .last(true)
+                            .help("Paths to restore, from the index or from the revision before '--'"),
+                    )
+                    .arg(
+                        Arg::new("new_branch")
+                            .short('b')
+                            .value_name("new-branch")
+                            .conflicts_with("force_new_branch")
+                            .help("Create a new branch at the start point (HEAD by default) and check it out"),
+                    )
+                    .arg(
+                        Arg::new("force_new_branch")
+                            .short('B')
+                            .value_name("new-branch")
+                            .help("Like -b, but reset the branch if it already exists"),
+                    )
+                    .arg(
+                        Arg::new("no_verify")
+                            .long("no-verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Bypass the pre-checkout hook"),
+                    ),
+            )
+            .subcommand(
+                Command::new("switch")
+                    .about("Switch branches, updating the working tree and the index")
+                    .arg(
+                        Arg::new("target")
+                            .help("Branch to switch to ('-' for the previous branch), the start point with -c, or the commit with --detach"),
+                    )
+                    .arg(
+                        Arg::new("create")
+                            .short('c')
+                            .long("create")
+                            .value_name("new-branch")
+                            .conflicts_with("force_create")
+                            .help("Create a new branch at the start point (HEAD by default) and switch to it"),
+                    )
+                    .arg(
+                        Arg::new("force_create")
+                            .short('C')
+                            .long("force-create")
+                            .value_name("new-branch")
+                            .help("Like -c, but reset the branch if it already exists"),
+                    )
                     .arg(
-                        Arg::new("name")
+                        Arg::new("detach")
+                            .short('d')
+                            .long("detach")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with_all(["create", "force_create"])
+                            .help("Detach HEAD at the commit"),
+                    )
+                    .arg(
+                        Arg::new("discard_changes")
+                            .short('f')
+                            .long("discard-changes")
+                            .visible_alias("force")
+                            .action(ArgAction::SetTrue)
+                            .help("Throw away local changes"),
+                    )
+                    .arg(
+                        Arg::new("no_verify")
+                            .long("no-verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Bypass the pre-checkout hook"),
+                    ),
+            )
+            .subcommand(
+                Command::new("restore")
+                    .about("Restore working tree files or unstage them")
+                    .arg(
+                        Arg::new("paths")
                             .required(true)
-                            .help("Branch name or commit key"),
+                            .num_args(1..)
+                            .help("Files or directories to restore"),
+                    )
+                    .arg(
+                        Arg::new("source")
+                            .short('s')
+                            .long("source")
+                            .value_name("tree-ish")
+                            .help("Take the files from this revision instead of the index (or HEAD with --staged)"),
+                    )
+                    .arg(
+                        Arg::new("staged")
+                            .short('S')
+                            .long("staged")
+                            .action(ArgAction::SetTrue)
+                            .help("Restore the index entries"),
+                    )
+                    .arg(
+                        Arg::new("worktree")
+                            .short('W')
+                            .long("worktree")
+                            .action(ArgAction::SetTrue)
+                            .help("Restore the working tree files (the default without --staged)"),
                     ),
             )
             .subcommand(
                 Command::new("branch")
-                    .about("List, create, or delete branches")
-                    .arg(Arg::new("name").required(false).help("Create new branch"))
+                    .about("List, create, rename, copy or delete branches")
+                    .arg(
+                        Arg::new("args")
+                            .num_args(0..)
+                            .help("<name> [<start-point>] to create, [<old>] <new> with -m or -c, globs with --list"),
+                    )
+                    .arg(
+                        Arg::new("force")
+                            .short('f')
+                            .long("force")
+                            .action(ArgAction::SetTrue)
+                            .help("Reset the branch to the start point if it already exists"),
+                    )
                     .arg(
                         Arg::new("delete_branch")
                             .required(false)
                             .short('d')
                             .long("delete")
-                            .help("Delete the branch"),
+                            .num_args(1..)
+                            .conflicts_with_all(["args", "force_delete"])
+                            .help("Delete the branches, they have to be merged into their upstream or HEAD"),
+                    )
+                    .arg(
+                        Arg::new("force_delete")
+                            .short('D')
+                            .num_args(1..)
+                            .conflicts_with("args")
+                            .help("Delete the branches even if they are not merged (same as -f -d)"),
+                    )
+                    .arg(
+                        Arg::new("move")
+                            .short('m')
+                            .long("move")
+                            .action(ArgAction::SetTrue)
+                            .help("Rename a branch (the current one by default), with its configuration"),
+                    )
+                    .arg(
+                        Arg::new("force_move")
+                            .short('M')
+                            .action(ArgAction::SetTrue)
+                            .help("Rename a branch even if the new name exists"),
+                    )
+                    .arg(
+                        Arg::new("copy")
+                            .short('c')
+                            .long("copy")
+                            .action(ArgAction::SetTrue)
+                            .help("Copy a branch (the current one by default), with its configuration"),
+                    )
+                    .arg(
+                        Arg::new("force_copy")
+                            .short('C')
+                            .action(ArgAction::SetTrue)
+                            .help("Copy a branch even if the new name exists"),
+                    )
+                    .arg(
+                        Arg::new("list")
+                            .short('l')
+                            .long("list")
+                            .action(ArgAction::SetTrue)
+                            .help("List branches, only those matching the globs when given"),
+                    )
+                    .arg(
+                        Arg::new("verbose")
+                            .short('v')
+                            .long("verbose")
+                            .action(ArgAction::Count)
+                            .help("Show the tip and subject of branches, twice for the upstream name"),
+                    )
+                    .arg(
+                        Arg::new("merged")
+                            .long("merged")
+                            .num_args(0..=1)
+                            .default_missing_value("HEAD")
+                            .help("Only list branches merged into the commit (default HEAD)"),
+                    )
+                    .arg(
+                        Arg::new("no_merged")
+                            .long("no-merged")
+                            .num_args(0..=1)
+                            .default_missing_value("HEAD")
+                            .help("Only list branches not merged into the commit (default HEAD)"),
+                    )
+                    .arg(
+                        Arg::new("contains")
+                            .long("contains")
+                            .num_args(0..=1)
+                            .default_missing_value("HEAD")
+                            .help("Only list branches containing the commit (default HEAD)"),
+                    )
+                    .arg(
+                        Arg::new("sort")
+                            .long("sort")
+                            .help("Sort by refname, committerdate, authordate or objectname, '-' for descending"),
+                    ),
+            )
+            .subcommand(
+                Command::new("check-ref-format")
+                    .about("Check that a reference name is well formed")
+                    .arg(Arg::new("refname").required(true).help("Reference name, e.g. refs/heads/main"))
+                    .arg(
+                        Arg::new("branch")
+                            .long("branch")
+                            .action(ArgAction::SetTrue)
+                            .help("Check a branch name and print it"),
+                    )
+                    .arg(
+                        Arg::new("allow_onelevel")
+                            .long("allow-onelevel")
+                            .action(ArgAction::SetTrue)
+                            .help("Accept names with a single component"),
+                    ),
+            )
+            .subcommand(
+                Command::new("symbolic-ref")
+                    .about("Read, create or delete symbolic references")
+                    .arg(Arg::new("name").required(true).help("Symbolic reference, e.g. HEAD"))
+                    .arg(Arg::new("target").help("Reference to point to, e.g. refs/heads/main"))
+                    .arg(
+                        Arg::new("short")
+                            .long("short")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the short name of the target"),
+                    )
+                    .arg(
+                        Arg::new("no_recurse")
+                            .long("no-recurse")
+                            .action(ArgAction::SetTrue)
+                            .help("Only follow one level of symbolic references"),
+                    )
+                    .arg(
+                        Arg::new("delete")
+                            .short('d')
+                            .long("delete")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("target")
+                            .help("Delete the symbolic reference"),
+                    )
+                    .arg(
+                        Arg::new("quiet")
+                            .short('q')
+                            .long("quiet")
+                            .action(ArgAction::SetTrue)
+                            .help("Do not print an error for references that are not symbolic"),
+                    )
+                    .arg(
+                        Arg::new("message")
+                            .short('m')
+                            .help("Reason recorded in the reflog when the reference changes"),
+                    ),
+            )
+            .subcommand(
+                Command::new("update-ref")
+                    .about("Update or delete a reference, optionally only from an expected value")
+                    .arg(
+                        Arg::new("refname")
+                            .required_unless_present("stdin")
+                            .help("Reference to update, e.g. refs/heads/main"),
+                    )
+                    .arg(Arg::new("new").help("New value (the old value with -d)"))
+                    .arg(Arg::new("old").help("Value the reference must hold, empty or null hash if it must not exist"))
+                    .arg(
+                        Arg::new("delete")
+                            .short('d')
+                            .action(ArgAction::SetTrue)
+                            .help("Delete the reference"),
+                    )
+                    .arg(
+                        Arg::new("no_deref")
+                            .long("no-deref")
+                            .action(ArgAction::SetTrue)
+                            .help("Update a symbolic reference itself instead of its target"),
+                    )
+                    .arg(
+                        Arg::new("message")
+                            .short('m')
+                            .help("Reason recorded in the reflog"),
+                    )
+                    .arg(
+                        Arg::new("stdin")
+                            .long("stdin")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with_all(["refname", "delete"])
+                            .help("Read update, create, delete and verify commands and apply them all or none"),
+                    ),
+            )
+            .subcommand(
+                Command::new("show-ref")
+                    .about("List references with the objects they point to")
+                    .arg(Arg::new("patterns").num_args(0..).help("Show references named by or ending in these patterns"))
+                    .arg(
+                        Arg::new("heads")
+                            .long("heads")
+                            .visible_alias("branches")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show branches"),
+                    )
+                    .arg(
+                        Arg::new("tags")
+                            .long("tags")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show tags"),
+                    )
+                    .arg(
+                        Arg::new("head")
+                            .long("head")
+                            .action(ArgAction::SetTrue)
+                            .help("Also show HEAD"),
+                    )
+                    .arg(
+                        Arg::new("verify")
+                            .long("verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Require the patterns to be existing full reference names"),
+                    )
+                    .arg(
+                        Arg::new("dereference")
+                            .short('d')
+                            .long("dereference")
+                            .action(ArgAction::SetTrue)
+                            .help("Also show the objects annotated tags point to"),
+                    )
+                    .arg(
+                        Arg::new("hash")
+                            .short('s')
+                            .long("hash")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show the object hashes"),
+                    )
+                    .arg(
+                        Arg::new("quiet")
+                            .short('q')
+                            .long("quiet")
+                            .action(ArgAction::SetTrue)
+                            .help("Print nothing, only set the exit status"),
+                    ),
+            )
+            .subcommand(
+                Command::new("for-each-ref")
+                    .about("List references with a custom format")
+                    .arg(Arg::new("patterns").num_args(0..).help("Reference prefixes (e.g. refs/heads) or globs"))
+                    .arg(
+                        Arg::new("format")
+                            .long("format")
+                            .help("Format with %(field) placeholders, e.g. '%(refname:short) %(objectname:short)'"),
+                    )
+                    .arg(
+                        Arg::new("sort")
+                            .long("sort")
+                            .action(ArgAction::Append)
+                            .help("Sort by a field, '-' prefix for descending order (the last key is the primary one)"),
+                    )
+                    .arg(
+                        Arg::new("count")
+                            .long("count")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Show at most this many references"),
+                    ),
+            )
+            .subcommand(
+                Command::new("reflog")
+                    .about("Show, expire or delete the entries of reference logs")
+                    .args_conflicts_with_subcommands(true)
+                    .arg(Arg::new("ref").default_value("HEAD").help("Reference whose log to show"))
+                    .arg(reflog_max_count())
+                    .subcommand(
+                        Command::new("show")
+                            .about("Show the log of a reference, newest entry first")
+                            .arg(Arg::new("ref").default_value("HEAD").help("Reference whose log to show"))
+                            .arg(reflog_max_count()),
+                    )
+                    .subcommand(
+                        Command::new("expire")
+                            .about("Remove old entries from reference logs")
+                            .arg(Arg::new("refs").num_args(0..).help("References whose logs to expire"))
+                            .arg(
+                                Arg::new("expire")
+                                    .long("expire")
+                                    .help("Remove entries older than this date ('never', 'all' or e.g. '90.days.ago')"),
+                            )
+                            .arg(
+                                Arg::new("expire_unreachable")
+                                    .long("expire-unreachable")
+                                    .help("Remove entries older than this date whose commit the reference no longer reaches"),
+                            )
+                            .arg(
+                                Arg::new("all")
+                                    .long("all")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Expire the logs of all references"),
+                            )
+                            .arg(
+                                Arg::new("dry_run")
+                                    .short('n')
+                                    .long("dry-run")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Only show the entries that would be removed"),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("delete")
+                            .about("Delete single entries from reference logs")
+                            .arg(
+                                Arg::new("entries")
+                                    .num_args(1..)
+                                    .required(true)
+                                    .help("Entries to delete, e.g. HEAD@{2}"),
+                            ),
                     ),
             )
             .subcommand(
@@ -70,6 +706,321 @@ fn main() {
                             .short('d')
                             .long("delete")
                             .help("Delete the tag"),
+                    )
+                    .arg(
+                        Arg::new("annotate")
+                            .short('a')
+                            .long("annotate")
+                            .action(ArgAction::SetTrue)
+                            .help("Create an annotated tag object"),
+                    )
+                    .arg(
+                        Arg::new("message")
+                            .short('m')
+                            .long("message")
+                            .help("Tag message"),
+                    )
+                    .arg(
+                        Arg::new("sign")
+                            .short('s')
+                            .long("sign")
+                            .action(ArgAction::SetTrue)
+                            .help("Create an SSH signed tag"),
+                    )
+                    .arg(
+                        Arg::new("local_user")
+                            .short('u')
+                            .long("local-user")
+                            .help("Create a signed tag with the given key file"),
+                    ),
+            )
+            .subcommand(
+                Command::new("verify-commit")
+                    .about("Check the SSH signature of commits")
+                    .arg(
+                        Arg::new("commit")
+                            .required(true)
+                            .action(ArgAction::Append)
+                            .help("Commits to verify"),
+                    ),
+            )
+            .subcommand(
+                Command::new("verify-tag")
+                    .about("Check the SSH signature of tags")
+                    .arg(
+                        Arg::new("tag")
+                            .required(true)
+                            .action(ArgAction::Append)
+                            .help("Tags to verify"),
+                    )
+                    .arg(
+                        Arg::new("verbose")
+                            .short('v')
+                            .long("verbose")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the contents of the tag object"),
+                    ),
+            )
+            .subcommand(
+                Command::new("show")
+                    .about("Show commits, tags, trees and blobs")
+                    .arg(
+                        Arg::new("objects")
+                            .num_args(0..)
+                            .default_value("HEAD")
+                            .help("Objects to show: <rev>, <rev>:<path> or :<path> (default HEAD)"),
+                    )
+                    .arg(
+                        Arg::new("format")
+                            .long("format")
+                            .help("Pretty-print commits with the given format string"),
+                    )
+                    .arg(Arg::new("pretty").long("pretty").help(
+                        "Pretty-print commits: oneline, short, medium, full, fuller, raw or format:<string>",
+                    ))
+                    .arg(
+                        Arg::new("oneline")
+                            .long("oneline")
+                            .action(ArgAction::SetTrue)
+                            .help("Shorthand for --pretty=oneline --abbrev-commit"),
+                    )
+                    .arg(
+                        Arg::new("abbrev_commit")
+                            .long("abbrev-commit")
+                            .action(ArgAction::SetTrue)
+                            .help("Show abbreviated commit hashes"),
+                    )
+                    .arg(Arg::new("date").long("date").help(
+                        "Date format: default, relative, local, iso, iso-strict, rfc, short, raw or unix",
+                    ))
+                    .arg(
+                        Arg::new("no_patch")
+                            .short('s')
+                            .long("no-patch")
+                            .action(ArgAction::SetTrue)
+                            .help("Do not show the patch of commits"),
+                    ),
+            )
+            .subcommand(
+                Command::new("blame")
+                    .about("Show what revision and author last modified each line of a file")
+                    .arg(
+                        Arg::new("args")
+                            .required(true)
+                            .num_args(1..=2)
+                            .value_names(["rev", "file"])
+                            .help("Optional revision to blame at (default HEAD) and the file"),
+                    )
+                    .arg(
+                        Arg::new("line_range")
+                            .short('L')
+                            .value_name("range")
+                            .action(ArgAction::Append)
+                            .help("Only blame the line range: <start>,<end> or :<funcname>"),
+                    )
+                    .arg(
+                        Arg::new("porcelain")
+                            .long("porcelain")
+                            .action(ArgAction::SetTrue)
+                            .help("Show the output in a format meant for scripts"),
+                    )
+                    .arg(
+                        Arg::new("line_porcelain")
+                            .long("line-porcelain")
+                            .action(ArgAction::SetTrue)
+                            .help("Show the porcelain format with the commit details on every line"),
+                    )
+                    .arg(
+                        Arg::new("ignore_whitespace")
+                            .short('w')
+                            .action(ArgAction::SetTrue)
+                            .help("Ignore whitespace when comparing lines"),
+                    )
+                    .arg(
+                        Arg::new("detect_moves")
+                            .short('M')
+                            .action(ArgAction::SetTrue)
+                            .help("Detect lines moved within the file"),
+                    )
+                    .arg(
+                        Arg::new("detect_copies")
+                            .short('C')
+                            .action(ArgAction::Count)
+                            .help("Detect lines copied from files changed in the same commit, twice for any file"),
+                    )
+                    .arg(
+                        Arg::new("ignore_rev")
+                            .long("ignore-rev")
+                            .action(ArgAction::Append)
+                            .help("Ignore the changes of the revision"),
+                    )
+                    .arg(
+                        Arg::new("ignore_revs_file")
+                            .long("ignore-revs-file")
+                            .action(ArgAction::Append)
+                            .help("Ignore the changes of the revisions listed in the file"),
+                    ),
+            )
+            .subcommand(
+                Command::new("shortlog")
+                    .about("Summarize the commits by author")
+                    .args(revision_args("Revisions or ranges to summarize (default HEAD, or a log on stdin)"))
+                    .arg(
+                        Arg::new("summary")
+                            .short('s')
+                            .long("summary")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show the number of commits"),
+                    )
+                    .arg(
+                        Arg::new("numbered")
+                            .short('n')
+                            .long("numbered")
+                            .action(ArgAction::SetTrue)
+                            .help("Sort by number of commits"),
+                    )
+                    .arg(
+                        Arg::new("email")
+                            .short('e')
+                            .long("email")
+                            .action(ArgAction::SetTrue)
+                            .help("Show the email address of each author"),
+                    )
+                    .arg(
+                        Arg::new("group")
+                            .long("group")
+                            .action(ArgAction::Append)
+                            .help("Group by author, committer or trailer:<key>"),
+                    ),
+            )
+            .subcommand(
+                Command::new("check-mailmap")
+                    .about("Show canonical names and emails of contacts from .mailmap")
+                    .arg(
+                        Arg::new("contacts")
+                            .num_args(0..)
+                            .help("Contacts as 'Name <email>' or '<email>'"),
+                    )
+                    .arg(
+                        Arg::new("stdin")
+                            .long("stdin")
+                            .action(ArgAction::SetTrue)
+                            .help("Also read contacts from standard input, one per line"),
+                    ),
+            )
+            .subcommand(
+                Command::new("rev-parse")
+                    .about("Resolve revision expressions to object hashes")
+                    .arg(
+                        Arg::new("args")
+                            .num_args(0..)
+                            .help("Revisions such as HEAD~2, main^2, @{-1}, v1.0^{tree} or A..B"),
+                    )
+                    .arg(
+                        Arg::new("abbrev_ref")
+                            .long("abbrev-ref")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the short name of references (the current branch for HEAD)"),
+                    )
+                    .arg(
+                        Arg::new("short")
+                            .long("short")
+                            .num_args(0..=1)
+                            .require_equals(true)
+                            .default_missing_value("7")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Print unique abbreviated hashes of at least this many digits"),
+                    )
+                    .arg(
+                        Arg::new("verify")
+                            .long("verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Check that exactly one argument names an existing object"),
+                    )
+                    .arg(
+                        Arg::new("quiet")
+                            .short('q')
+                            .long("quiet")
+                            .action(ArgAction::SetTrue)
+                            .help("With --verify, print no error for invalid revisions"),
+                    )
+                    .arg(
+                        Arg::new("show_toplevel")
+                            .long("show-toplevel")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the top-level directory of the working tree"),
+                    ),
+            )
+            .subcommand(
+                Command::new("rev-list")
+                    .about("List commits reachable from revisions, newest first")
+                    .args(revision_args("Revisions or ranges to list, e.g. main, A..B, A...B or ^A"))
+                    .arg(
+                        Arg::new("count")
+                            .long("count")
+                            .action(ArgAction::SetTrue)
+                            .help("Only print the number of commits"),
+                    )
+                    .arg(
+                        Arg::new("left_right")
+                            .long("left-right")
+                            .action(ArgAction::SetTrue)
+                            .help("Mark commits with < or > for the side of A...B they come from"),
+                    )
+                    .arg(
+                        Arg::new("objects")
+                            .long("objects")
+                            .action(ArgAction::SetTrue)
+                            .help("Also list the trees and blobs of the listed commits"),
+                    )
+                    .arg(
+                        Arg::new("parents")
+                            .long("parents")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the parents of every commit"),
+                    )
+                    .arg(
+                        Arg::new("first_parent")
+                            .long("first-parent")
+                            .action(ArgAction::SetTrue)
+                            .help("Follow only the first parent of merge commits"),
+                    )
+                    .arg(
+                        Arg::new("topo_order")
+                            .long("topo-order")
+                            .action(ArgAction::SetTrue)
+                            .help("Show no parents before all of their children"),
+                    )
+                    .arg(
+                        Arg::new("reverse")
+                            .long("reverse")
+                            .action(ArgAction::SetTrue)
+                            .help("Output the commits in reverse order"),
+                    )
+                    .arg(
+                        Arg::new("ancestry_path")
+                            .long("ancestry-path")
+                            .action(ArgAction::SetTrue)
+                            .help("Only list descendants of the excluded commits"),
+                    )
+                    .arg(
+                        Arg::new("max_count")
+                            .short('n')
+                            .long("max-count")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Limit the number of commits to output"),
+                    )
+                    .arg(
+                        Arg::new("skip")
+                            .long("skip")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Skip the given number of commits before output"),
+                    )
+                    .arg(
+                        Arg::new("paths")
+                            .num_args(1..)
+                            .last(true)
+                            .help("Only list commits changing the given paths"),
                     ),
             )
             .subcommand(Command::new("status").about("Show the working tree status"))
@@ -80,7 +1031,100 @@ fn main() {
             .subcommand(
                 Command::new("commit")
                     .about("Record changes to the repository")
-                    .arg(Arg::new("message").required(true).help("Commit message")),
+                    .arg(Arg::new("message").required(true).help("Commit message"))
+                    .arg(
+                        Arg::new("signoff")
+                            .short('s')
+                            .long("signoff")
+                            .action(ArgAction::SetTrue)
+                            .help("Add a Signed-off-by trailer by the committer"),
+                    )
+                    .arg(
+                        Arg::new("trailer")
+                            .long("trailer")
+                            .action(ArgAction::Append)
+                            .help("Add a trailer to the commit message (<token>=<value>)"),
+                    )
+                    .arg(
+                        Arg::new("gpg_sign")
+                            .short('S')
+                            .long("gpg-sign")
+                            .num_args(0..=1)
+                            .require_equals(true)
+                            .default_missing_value("")
+                            .help("SSH sign the commit, optionally with the given key file"),
+                    )
+                    .arg(
+                        Arg::new("no_verify")
+                            .short('n')
+                            .long("no-verify")
+                            .action(ArgAction::SetTrue)
+                            .help("Bypass the pre-commit and commit-msg hooks"),
+                    ),
+            )
+            .subcommand(
+                Command::new("interpret-trailers")
+                    .about("Add or parse structured information in commit messages")
+                    .arg(
+                        Arg::new("file")
+                            .action(ArgAction::Append)
+                            .help("Files to read the messages from (default stdin)"),
+                    )
+                    .arg(
+                        Arg::new("trailer")
+                            .long("trailer")
+                            .action(ArgAction::Append)
+                            .help("Trailer to add (<token>=<value>)"),
+                    )
+                    .arg(
+                        Arg::new("where")
+                            .long("where")
+                            .help("Where to place new trailers: end, start, after or before"),
+                    )
+                    .arg(Arg::new("if_exists").long("if-exists").help(
+                        "Action when the trailer already exists: addIfDifferentNeighbor, addIfDifferent, add, replace or doNothing",
+                    ))
+                    .arg(
+                        Arg::new("if_missing")
+                            .long("if-missing")
+                            .help("Action when the trailer is missing: add or doNothing"),
+                    )
+                    .arg(
+                        Arg::new("in_place")
+                            .long("in-place")
+                            .action(ArgAction::SetTrue)
+                            .help("Edit the files in place"),
+                    )
+                    .arg(
+                        Arg::new("trim_empty")
+                            .long("trim-empty")
+                            .action(ArgAction::SetTrue)
+                            .help("Remove trailers with empty values"),
+                    )
+                    .arg(
+                        Arg::new("only_trailers")
+                            .long("only-trailers")
+                            .action(ArgAction::SetTrue)
+                            .help("Output only the trailers"),
+                    )
+                    .arg(
+                        Arg::new("only_input")
+                            .long("only-input")
+                            .action(ArgAction::SetTrue)
+                            .help("Do not add any new trailers"),
+                    )
+                    .arg(
+                        Arg::new("unfold")
+                            .long("unfold")
+                            .action(ArgAction::SetTrue)
+                            .help("Join multi-line trailer values"),
+                    )
+                    .arg(
+                        Arg::new("parse")
+                            .long("parse")
+                            .action(ArgAction::SetTrue)
+                            .help("Alias for --only-trailers --only-input --unfold"),
+                    ),
             )
             .subcommand(
                 Command::new("config")
@@ -89,9 +1133,9 @@ fn main() {
                         Command::new("set")
                             .about("Set configuration for the repository")
                             .arg(
-                                Arg::new("key")
-                                    .required(true)
-                                    .help("The configuration key (e.g., 'name' or 'email')"),
+                                Arg::new("key").required(true).help(
+                                    "The configuration key (e.g., 'name', 'email' or 'trailer.sign.key')",
+                                ),
                             )
                             .arg(Arg::new("value").required(true).help(
                                 "The value to set (e.g., 'John Doe' or 'johndoe@example.com')",
@@ -127,66 +1171,592 @@ fn main() {
             .get_matches();
 
     // Handle the init command
-    if let Some(_) = matches.subcommand_matches("init") {
+    if matches.subcommand_matches("init").is_some() {
         if let Err(e) = init() {
             eprintln!("Error initializing repository: {}", e);
         }
     }
     // Handle the log command
-    if let Some(_) = matches.subcommand_matches("log") {
-        if let Err(e) = log() {
+    if let Some(log_matches) = matches.subcommand_matches("log") {
+        let result = (|| {
+            let oneline = log_matches.get_flag("oneline");
+            let pretty = if let Some(format) = log_matches.get_one::<String>("format") {
+                Some(PrettyFormat::parse(&format!("tformat:{}", format))?)
+            } else if let Some(pretty) = log_matches.get_one::<String>("pretty") {
+                Some(PrettyFormat::parse(pretty)?)
+            } else if oneline {
+                Some(PrettyFormat::Oneline)
+            } else {
+                None
+            };
+
+            let ignore_case = log_matches.get_flag("regexp_ignore_case");
+            let patterns = |name: &str| -> Vec<String> {
+                log_matches
+                    .get_many::<String>(name)
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect()
+            };
+
+            let options = LogOptions {
+                revisions: ordered_revisions(log_matches),
+                pretty,
+                date_mode: log_matches
+                    .get_one::<String>("date")
+                    .map(|d| DateMode::parse(d))
+                    .transpose()?
+                    .unwrap_or_default(),
+                abbrev_commit: oneline || log_matches.get_flag("abbrev_commit"),
+                decorate: match log_matches
+                    .get_one::<String>("decorate")
+                    .map(|d| d.as_str())
+                {
+                    Some("short") => Some(false),
+                    Some("full") => Some(true),
+                    _ => None,
+                },
+                graph: log_matches.get_flag("graph"),
+                show_signature: log_matches.get_flag("show_signature"),
+                use_mailmap: if log_matches.get_flag("use_mailmap") {
+                    Some(true)
+                } else if log_matches.get_flag("no_use_mailmap") {
+                    Some(false)
+                } else {
+                    None
+                },
+                patch: log_matches.get_flag("patch"),
+                pickaxe_all: log_matches.get_flag("pickaxe_all"),
+                line_ranges: log_matches
+                    .get_many::<String>("line_range")
+                    .unwrap_or_default()
+                    .map(|range| LineRangeSpec::parse(range))
+                    .collect::<std::io::Result<_>>()?,
+                walk: WalkOptions {
+                    first_parent: log_matches.get_flag("first_parent"),
+                    topo_order: log_matches.get_flag("topo_order"),
+                    reverse: log_matches.get_flag("reverse"),
+                    paths: log_matches
+                        .get_many::<String>("paths")
+                        .unwrap_or_default()
+                        .cloned()
+                        .collect(),
+                    filter: CommitFilter {
+                        authors: compile_patterns(&patterns("author"), ignore_case)?,
+                        committers: compile_patterns(&patterns("committer"), ignore_case)?,
+                        grep: compile_patterns(&patterns("grep"), ignore_case)?,
+                        all_match: log_matches.get_flag("all_match"),
+                        invert_grep: log_matches.get_flag("invert_grep"),
+                        since: log_matches
+                            .get_one::<String>("since")
+                            .map(|d| parse_date(d))
+                            .transpose()?,
+                        until: log_matches
+                            .get_one::<String>("until")
+                            .map(|d| parse_date(d))
+                            .transpose()?,
+                        pickaxe: if let Some(string) =
+                            log_matches.get_one::<String>("pickaxe_string")
+                        {
+                            let pattern = compile_patterns(&[regex::escape(string)], ignore_case)?;
+                            pattern.into_iter().next().map(Pickaxe::Occurrences)
+                        } else if let Some(regex) = log_matches.get_one::<String>("pickaxe_regex") {
+                            let pattern =
+                                compile_patterns(std::slice::from_ref(regex), ignore_case)?;
+                            pattern.into_iter().next().map(Pickaxe::Lines)
+                        } else {
+                            None
+                        },
+                    },
+                    skip: log_matches.get_one::<usize>("skip").copied().unwrap_or(0),
+                    max_count: log_matches.get_one::<usize>("max_count").copied(),
+                    exclude: vec![],
+                    ancestry_path: log_matches.get_flag("ancestry_path"),
+                },
+            };
+            log(&options)
+        })();
+        if let Err(e) = result {
             eprintln!("Error when retrieve commit logs: {}", e);
         }
     }
 
+    // Handle the show command
+    if let Some(show_matches) = matches.subcommand_matches("show") {
+        let result = (|| {
+            let oneline = show_matches.get_flag("oneline");
+            let pretty = if let Some(format) = show_matches.get_one::<String>("format") {
+                Some(PrettyFormat::parse(&format!("tformat:{}", format))?)
+            } else if let Some(pretty) = show_matches.get_one::<String>("pretty") {
+                Some(PrettyFormat::parse(pretty)?)
+            } else if oneline {
+                Some(PrettyFormat::Oneline)
+            } else {
+                None
+            };
+
+            let options = ShowOptions {
+                pretty,
+                date_mode: show_matches
+                    .get_one::<String>("date")
+                    .map(|d| DateMode::parse(d))
+                    .transpose()?
+                    .unwrap_or_default(),
+                abbrev_commit: oneline || show_matches.get_flag("abbrev_commit"),
+                no_patch: show_matches.get_flag("no_patch"),
+            };
+            let objects: Vec<String> = show_matches
+                .get_many::<String>("objects")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            show(&objects, &options)
+        })();
+        if let Err(e) = result {
+            eprintln!("Error showing object: {}", e);
+        }
+    }
+
+    // Handle the blame command
+    if let Some(blame_matches) = matches.subcommand_matches("blame") {
+        let mut args: Vec<String> = blame_matches
+            .get_many::<String>("args")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        let file = args.pop().unwrap_or_default();
+        let values = |name: &str| -> Vec<String> {
+            blame_matches
+                .get_many::<String>(name)
+                .unwrap_or_default()
+                .cloned()
+                .collect()
+        };
+        let options = BlameOptions {
+            revision: args.pop(),
+            ranges: values("line_range"),
+            porcelain: blame_matches.get_flag("porcelain"),
+            line_porcelain: blame_matches.get_flag("line_porcelain"),
+            ignore_whitespace: blame_matches.get_flag("ignore_whitespace"),
+            detect_moves: blame_matches.get_flag("detect_moves"),
+            detect_copies: blame_matches.get_count("detect_copies"),
+            ignore_revs: values("ignore_rev"),
+            ignore_revs_files: values("ignore_revs_file"),
+        };
+        if let Err(e) = blame(&file, &options) {
+            eprintln!("Error blaming file: {}", e);
+        }
+    }
+
+    // Handle the shortlog command
+    if let Some(shortlog_matches) = matches.subcommand_matches("shortlog") {
+        let result = (|| {
+            let options = ShortlogOptions {
+                revisions: ordered_revisions(shortlog_matches),
+                summary: shortlog_matches.get_flag("summary"),
+                numbered: shortlog_matches.get_flag("numbered"),
+                email: shortlog_matches.get_flag("email"),
+                groups: shortlog_matches
+                    .get_many::<String>("group")
+                    .unwrap_or_default()
+                    .map(|g| ShortlogGroup::parse(g))
+                    .collect::<std::io::Result<_>>()?,
+            };
+            shortlog(&options)
+        })();
+        if let Err(e) = result {
+            eprintln!("Error summarizing commits: {}", e);
+        }
+    }
+
+    // Handle the check-mailmap command
+    if let Some(check_matches) = matches.subcommand_matches("check-mailmap") {
+        let contacts: Vec<String> = check_matches
+            .get_many::<String>("contacts")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        if let Err(e) = check_mailmap(&contacts, check_matches.get_flag("stdin")) {
+            eprintln!("Error checking mailmap: {}", e);
+        }
+    }
+
+    // Handle the rev-parse command
+    if let Some(rev_parse_matches) = matches.subcommand_matches("rev-parse") {
+        let args: Vec<String> = rev_parse_matches
+            .get_many::<String>("args")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        let options = RevParseOptions {
+            abbrev_ref: rev_parse_matches.get_flag("abbrev_ref"),
+            short: rev_parse_matches.get_one::<usize>("short").copied(),
+            verify: rev_parse_matches.get_flag("verify"),
+            show_toplevel: rev_parse_matches.get_flag("show_toplevel"),
+        };
+        if let Err(e) = rev_parse(&args, &options) {
+            if !(options.verify && rev_parse_matches.get_flag("quiet")) {
+                eprintln!("Error parsing revision: {}", e);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the rev-list command
+    if let Some(rev_list_matches) = matches.subcommand_matches("rev-list") {
+        let options = RevListOptions {
+            revisions: ordered_revisions(rev_list_matches),
+            count: rev_list_matches.get_flag("count"),
+            left_right: rev_list_matches.get_flag("left_right"),
+            objects: rev_list_matches.get_flag("objects"),
+            parents: rev_list_matches.get_flag("parents"),
+            walk: WalkOptions {
+                first_parent: rev_list_matches.get_flag("first_parent"),
+                topo_order: rev_list_matches.get_flag("topo_order"),
+                reverse: rev_list_matches.get_flag("reverse"),
+                ancestry_path: rev_list_matches.get_flag("ancestry_path"),
+                paths: rev_list_matches
+                    .get_many::<String>("paths")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+                skip: rev_list_matches
+                    .get_one::<usize>("skip")
+                    .copied()
+                    .unwrap_or(0),
+                max_count: rev_list_matches.get_one::<usize>("max_count").copied(),
+                ..WalkOptions::default()
+            },
+        };
+        if let Err(e) = rev_list(&options) {
+            eprintln!("Error listing revisions: {}", e);
+            std::process::exit(1);
+        }
+    }
+
     // Handle the status command
-    if let Some(_) = matches.subcommand_matches("status") {
+    if matches.subcommand_matches("status").is_some() {
         if let Err(e) = status() {
             eprintln!("Error when retrieve the status of repository: {}", e);
+            std::process::exit(1);
         }
     }
 
     // Handle the diff command
-    if let Some(_) = matches.subcommand_matches("diff") {
+    if matches.subcommand_matches("diff").is_some() {
         if let Err(e) = diff() {
             eprintln!("Error when retrieve the changes of working tree: {}", e);
+            std::process::exit(1);
         }
     }
 
     // Handle the add command
     if let Some(add_matches) = matches.subcommand_matches("add") {
         if let Some(file_path) = add_matches.get_one::<String>("file") {
-            if let Err(e) = add(file_path) {
+            if let Err(e) = add(file_path, add_matches.get_flag("no_verify")) {
                 eprintln!("Error adding file to the staging area: {}", e);
+                std::process::exit(1);
             }
         }
     }
 
     // Handle the checkout command
     if let Some(checkout_matches) = matches.subcommand_matches("checkout") {
-        if let Some(name) = checkout_matches.get_one::<String>("name") {
-            if let Err(e) = checkout(name) {
-                eprintln!("{}", e);
-            }
+        let args: Vec<String> = checkout_matches
+            .get_many::<String>("args")
+            .map(|args| args.cloned().collect())
+            .unwrap_or_default();
+        // Arguments after "--" are always paths
+        let paths: Option<Vec<String>> = checkout_matches
+            .get_many::<String>("paths")
+            .map(|paths| paths.cloned().collect());
+        let force_new_branch = checkout_matches.get_one::<String>("force_new_branch");
+        let options = CheckoutOptions {
+            new_branch: checkout_matches
+                .get_one::<String>("new_branch")
+                .or(force_new_branch)
+                .cloned(),
+            force_new_branch: force_new_branch.is_some(),
+            no_verify: checkout_matches.get_flag("no_verify"),
+        };
+        if let Err(e) = checkout(&args, paths.as_deref(), &options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the switch command
+    if let Some(switch_matches) = matches.subcommand_matches("switch") {
+        let force_create = switch_matches.get_one::<String>("force_create");
+        let options = SwitchOptions {
+            create: switch_matches
+                .get_one::<String>("create")
+                .or(force_create)
+                .cloned(),
+            force_create: force_create.is_some(),
+            detach: switch_matches.get_flag("detach"),
+            discard_changes: switch_matches.get_flag("discard_changes"),
+            no_verify: switch_matches.get_flag("no_verify"),
+        };
+        let target = switch_matches.get_one::<String>("target");
+        if let Err(e) = switch(target.map(|t| t.as_str()), &options) {
+            eprintln!("Error switching branches: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the restore command
+    if let Some(restore_matches) = matches.subcommand_matches("restore") {
+        let paths: Vec<String> = restore_matches
+            .get_many::<String>("paths")
+            .unwrap()
+            .cloned()
+            .collect();
+        let options = RestoreOptions {
+            source: restore_matches.get_one::<String>("source").cloned(),
+            staged: restore_matches.get_flag("staged"),
+            worktree: restore_matches.get_flag("worktree"),
+            overlay: false,
+        };
+        if let Err(e) = restore(&paths, &options) {
+            eprintln!("Error restoring files: {}", e);
+            std::process::exit(1);
         }
     }
 
     // Handle the branch command
     if let Some(branch_matches) = matches.subcommand_matches("branch") {
-        let delete_branch_name = branch_matches.get_one::<String>("delete_branch");
+        let delete_branch_names = branch_matches
+            .get_many::<String>("delete_branch")
+            .or(branch_matches.get_many::<String>("force_delete"));
 
         // Delete branch case
-        if let Some(branch_name) = delete_branch_name {
-            if let Err(e) = delete_branch(branch_name) {
+        if let Some(branch_names) = delete_branch_names {
+            let branch_names: Vec<String> = branch_names.cloned().collect();
+            let force =
+                branch_matches.contains_id("force_delete") || branch_matches.get_flag("force");
+            if let Err(e) = delete_branch(&branch_names, force) {
                 eprintln!("Error when deleting the branch: {}", e);
+                std::process::exit(1);
+            }
+        } else {
+            let result = {
+                let args: Vec<String> = branch_matches
+                    .get_many::<String>("args")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect();
+                let force_move = branch_matches.get_flag("force_move");
+                let force_copy = branch_matches.get_flag("force_copy");
+                let listing = branch_matches.get_flag("list")
+                    || branch_matches.get_count("verbose") > 0
+                    || ["merged", "no_merged", "contains", "sort"]
+                        .iter()
+                        .any(|id| branch_matches.contains_id(id));
+
+                if branch_matches.get_flag("move")
+                    || force_move
+                    || branch_matches.get_flag("copy")
+                    || force_copy
+                {
+                    // Rename or copy case: [<old>] <new>
+                    let copy = branch_matches.get_flag("copy") || force_copy;
+                    let force = force_move || force_copy || branch_matches.get_flag("force");
+                    match args.as_slice() {
+                        [new] => rename_branch(None, new, copy, force),
+                        [old, new] => rename_branch(Some(old), new, copy, force),
+                        _ => Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "Give [<old>] <new> to rename or copy a branch",
+                        )),
+                    }
+                } else if listing || args.is_empty() {
+                    // List branch case
+                    let options = BranchListOptions {
+                        verbose: branch_matches.get_count("verbose"),
+                        merged: branch_matches.get_one::<String>("merged").cloned(),
+                        no_merged: branch_matches.get_one::<String>("no_merged").cloned(),
+                        contains: branch_matches.get_one::<String>("contains").cloned(),
+                        sort: branch_matches.get_one::<String>("sort").cloned(),
+                        patterns: args,
+                    };
+                    list_branches(&options)
+                } else {
+                    // Create branch case: <name> [<start-point>]
+                    let options = BranchOptions {
+                        start_point: args.get(1).cloned(),
+                        force: branch_matches.get_flag("force"),
+                    };
+                    match args.as_slice() {
+                        [name] | [name, _] => branch(name, &options),
+                        _ => Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "Give <name> [<start-point>] to create a branch",
+                        )),
+                    }
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Error with the branch command: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Handle the check-ref-format command
+    if let Some(check_matches) = matches.subcommand_matches("check-ref-format") {
+        if let Some(refname) = check_matches.get_one::<String>("refname") {
+            let result = if check_matches.get_flag("branch") {
+                check_branch_name(refname).map(|_| println!("{}", refname))
+            } else if !refname.contains('/') && !check_matches.get_flag("allow_onelevel") {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "'{}' has a single component (use --allow-onelevel)",
+                        refname
+                    ),
+                ))
+            } else {
+                check_ref_format(refname)
+            };
+            if let Err(e) = result {
+                eprintln!("Error checking reference name: {}", e);
+                std::process::exit(1);
             }
+        }
+    }
+
+    // Handle the symbolic-ref command
+    if let Some(symref_matches) = matches.subcommand_matches("symbolic-ref") {
+        let name = symref_matches.get_one::<String>("name").unwrap();
+        let target = symref_matches.get_one::<String>("target");
+        let options = SymbolicRefOptions {
+            short: symref_matches.get_flag("short"),
+            no_recurse: symref_matches.get_flag("no_recurse"),
+            delete: symref_matches.get_flag("delete"),
+            message: symref_matches.get_one::<String>("message").cloned(),
+        };
+        if let Err(e) = symbolic_ref(name, target.map(|t| t.as_str()), &options) {
+            if !symref_matches.get_flag("quiet") {
+                eprintln!("Error with the symbolic reference: {}", e);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the update-ref command
+    if let Some(update_matches) = matches.subcommand_matches("update-ref") {
+        let options = UpdateRefOptions {
+            delete: update_matches.get_flag("delete"),
+            no_deref: update_matches.get_flag("no_deref"),
+            message: update_matches
+                .get_one::<String>("message")
+                .cloned()
+                .unwrap_or_default(),
+        };
+        let new = update_matches.get_one::<String>("new").map(|n| n.as_str());
+        let old = update_matches.get_one::<String>("old").map(|o| o.as_str());
+        // With -d the only value given is the old one
+        let (new, old) = if options.delete {
+            (None, new)
         } else {
-            // Create or list branch case
-            let new_branch_name = branch_matches.get_one::<String>("name");
+            (new, old)
+        };
+        let result = match update_matches.get_one::<String>("refname") {
+            Some(refname) => update_ref(refname, new, old, &options),
+            None => update_refs_from_stdin(&options),
+        };
+        if let Err(e) = result {
+            eprintln!("Error updating the reference: {}", e);
+            std::process::exit(1);
+        }
+    }
 
-            if let Err(e) = branch(new_branch_name) {
-                eprintln!("Error when creating new branch: {}", e);
+    // Handle the show-ref command
+    if let Some(show_ref_matches) = matches.subcommand_matches("show-ref") {
+        let patterns: Vec<String> = show_ref_matches
+            .get_many::<String>("patterns")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        let options = ShowRefOptions {
+            heads: show_ref_matches.get_flag("heads"),
+            tags: show_ref_matches.get_flag("tags"),
+            head: show_ref_matches.get_flag("head"),
+            verify: show_ref_matches.get_flag("verify"),
+            dereference: show_ref_matches.get_flag("dereference"),
+            hash: show_ref_matches.get_flag("hash"),
+            quiet: show_ref_matches.get_flag("quiet"),
+        };
+        if let Err(e) = show_ref(&patterns, &options) {
+            if !options.quiet {
+                eprintln!("Error showing references: {}", e);
             }
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the for-each-ref command
+    if let Some(for_each_matches) = matches.subcommand_matches("for-each-ref") {
+        let options = ForEachRefOptions {
+            format: for_each_matches.get_one::<String>("format").cloned(),
+            sort: for_each_matches
+                .get_many::<String>("sort")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+            count: for_each_matches.get_one::<usize>("count").copied(),
+            patterns: for_each_matches
+                .get_many::<String>("patterns")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+        };
+        if let Err(e) = for_each_ref(&options) {
+            eprintln!("Error listing references: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the reflog command, `reflog <ref>` is the same as `reflog show <ref>`
+    if let Some(reflog_matches) = matches.subcommand_matches("reflog") {
+        let result = match reflog_matches.subcommand() {
+            Some(("expire", expire_matches)) => {
+                let refs: Vec<String> = expire_matches
+                    .get_many::<String>("refs")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect();
+                let options = ReflogExpireOptions {
+                    expire: expire_matches.get_one::<String>("expire").cloned(),
+                    expire_unreachable: expire_matches
+                        .get_one::<String>("expire_unreachable")
+                        .cloned(),
+                    all: expire_matches.get_flag("all"),
+                    dry_run: expire_matches.get_flag("dry_run"),
+                };
+                reflog_expire(&refs, &options)
+            }
+            Some(("delete", delete_matches)) => {
+                let entries: Vec<String> = delete_matches
+                    .get_many::<String>("entries")
+                    .unwrap()
+                    .cloned()
+                    .collect();
+                reflog_delete(&entries)
+            }
+            Some((_, show_matches)) => reflog_show(
+                show_matches.get_one::<String>("ref").unwrap(),
+                show_matches.get_one::<usize>("max_count").copied(),
+            ),
+            None => reflog_show(
+                reflog_matches.get_one::<String>("ref").unwrap(),
+                reflog_matches.get_one::<usize>("max_count").copied(),
+            ),
+        };
+        if let Err(e) = result {
+            eprintln!("Error with the reflog: {}", e);
+            std::process::exit(1);
         }
     }
 
@@ -197,12 +1767,18 @@ fn main() {
 
         if let Some(delete_tag_file) = delete_tag_name {
             // Delete branch case
-            if let Err(e) = delete_tag(&delete_tag_file) {
+            if let Err(e) = delete_tag(delete_tag_file) {
                 eprintln!("Error when deleting the tag: {}", e);
             }
         } else {
             if let Some(tag_name) = new_tag_name {
-                if let Err(e) = tag(tag_name) {
+                let options = TagOptions {
+                    annotate: tag_matches.get_flag("annotate"),
+                    message: tag_matches.get_one::<String>("message").cloned(),
+                    sign: tag_matches.get_flag("sign") || tag_matches.contains_id("local_user"),
+                    signing_key: tag_matches.get_one::<String>("local_user").cloned(),
+                };
+                if let Err(e) = tag(tag_name, &options) {
                     eprintln!("Error when creating new tag: {}", e);
                 }
             } else {
@@ -216,25 +1792,110 @@ fn main() {
     // Handle the commit command
     if let Some(commit_matches) = matches.subcommand_matches("commit") {
         if let Some(message) = commit_matches.get_one::<String>("message") {
-            if let Err(e) = commit(message) {
+            let options = CommitOptions {
+                signoff: commit_matches.get_flag("signoff"),
+                trailers: commit_matches
+                    .get_many::<String>("trailer")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+                sign: commit_matches.contains_id("gpg_sign"),
+                signing_key: commit_matches.get_one::<String>("gpg_sign").cloned(),
+                no_verify: commit_matches.get_flag("no_verify"),
+            };
+            if let Err(e) = commit(message, &options) {
                 eprintln!("Error committing file to the repository: {}", e);
+                std::process::exit(1);
             }
         }
     }
 
+    // Handle the verify-commit command
+    if let Some(verify_matches) = matches.subcommand_matches("verify-commit") {
+        let commits: Vec<String> = verify_matches
+            .get_many::<String>("commit")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        if let Err(e) = verify_commit(&commits) {
+            eprintln!("Error verifying commit: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the verify-tag command
+    if let Some(verify_matches) = matches.subcommand_matches("verify-tag") {
+        let tags: Vec<String> = verify_matches
+            .get_many::<String>("tag")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        if let Err(e) = verify_tag(&tags, verify_matches.get_flag("verbose")) {
+            eprintln!("Error verifying tag: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Handle the interpret-trailers command
+    if let Some(trailer_matches) = matches.subcommand_matches("interpret-trailers") {
+        let files: Vec<String> = trailer_matches
+            .get_many::<String>("file")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        let parse = trailer_matches.get_flag("parse");
+
+        let result = (|| {
+            let placement = TrailerOptions {
+                where_: trailer_matches
+                    .get_one::<String>("where")
+                    .map(|w| TrailerWhere::parse(w))
+                    .transpose()?,
+                if_exists: trailer_matches
+                    .get_one::<String>("if_exists")
+                    .map(|v| TrailerIfExists::parse(v))
+                    .transpose()?,
+                if_missing: trailer_matches
+                    .get_one::<String>("if_missing")
+                    .map(|v| TrailerIfMissing::parse(v))
+                    .transpose()?,
+            };
+            let options = InterpretTrailersOptions {
+                trailers: trailer_matches
+                    .get_many::<String>("trailer")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+                placement,
+                in_place: trailer_matches.get_flag("in_place"),
+                trim_empty: trailer_matches.get_flag("trim_empty"),
+                only_trailers: parse || trailer_matches.get_flag("only_trailers"),
+                only_input: parse || trailer_matches.get_flag("only_input"),
+                unfold: parse || trailer_matches.get_flag("unfold"),
+            };
+            interpret_trailers(&files, &options)
+        })();
+
+        if let Err(e) = result {
+            eprintln!("Error interpreting trailers: {}", e);
+        }
+    }
+
     // Handle the config set and get command
     if let Some(config_matches) = matches.subcommand_matches("config") {
         if let Some(set_matches) = config_matches.subcommand_matches("set") {
             let key = set_matches.get_one::<String>("key").unwrap();
             let value = set_matches.get_one::<String>("value").unwrap();
-            if let Err(err) = handle_config_command("set", key, "[user]", Some(value)) {
+            let (section_name, key) = parse_config_key(key);
+            if let Err(err) = handle_config_command("set", &key, &section_name, Some(value)) {
                 eprintln!("Error setting configuration: {}", err);
             }
         }
 
         if let Some(get_matches) = config_matches.subcommand_matches("get") {
             let key = get_matches.get_one::<String>("key").unwrap();
-            if let Err(err) = handle_config_command("get", key, "[user]", None) {
+            let (section_name, key) = parse_config_key(key);
+            if let Err(err) = handle_config_command("get", &key, &section_name, None) {
                 eprintln!("Error getting configuration: {}", err);
             }
         }
@@ -245,16 +1906,82 @@ fn main() {
         if let Some(add_matches) = remote_matches.subcommand_matches("add") {
             let name = add_matches.get_one::<String>("name").unwrap();
             let url = add_matches.get_one::<String>("url").unwrap();
-            if let Err(err) = add_remote(&name, &url) {
+            if let Err(err) = add_remote(name, url) {
                 eprintln!("Error adding remote configuration: {}", err);
             }
         }
 
         if let Some(remove_matches) = remote_matches.subcommand_matches("remove") {
             let name = remove_matches.get_one::<String>("name").unwrap();
-            if let Err(err) = remove_remote(&name) {
+            if let Err(err) = remove_remote(name) {
                 eprintln!("Error removing remote configuration: {}", err);
             }
         }
     }
 }
+
+// Number of entries shown by `reflog` and `reflog show`
+fn reflog_max_count() -> Arg {
+    Arg::new("max_count")
+        .short('n')
+        .long("max-count")
+        .value_parser(clap::value_parser!(usize))
+        .help("Limit the number of entries to output")
+}
+
+// Revision arguments of the history commands, "--not", "--all", "--branches" and "--tags"
+// are kept in command line order by `ordered_revisions`
+fn revision_args(help: &'static str) -> [Arg; 5] {
+    [
+        Arg::new("revisions").num_args(0..).help(help),
+        Arg::new("not")
+            .long("not")
+            .num_args(0)
+            .default_missing_value("--not")
+            .action(ArgAction::Append)
+            .help("Flip the meaning of the following revisions (as with a '^' prefix)"),
+        Arg::new("all")
+            .long("all")
+            .num_args(0)
+            .default_missing_value("--all")
+            .action(ArgAction::Append)
+            .help("Start from HEAD and every reference"),
+        Arg::new("branches")
+            .long("branches")
+            .num_args(0..=1)
+            .require_equals(true)
+            .value_name("glob")
+            .default_missing_value("*")
+            .action(ArgAction::Append)
+            .help("Start from every branch, or the branches matching the glob"),
+        Arg::new("tags")
+            .long("tags")
+            .num_args(0..=1)
+            .require_equals(true)
+            .value_name("glob")
+            .default_missing_value("*")
+            .action(ArgAction::Append)
+            .help("Start from every tag, or the tags matching the glob"),
+    ]
+}
+
+// Revision arguments in the order they were given, as understood by `parse_revision_args`
+fn ordered_revisions(matches: &ArgMatches) -> Vec<String> {
+    let mut args: Vec<(usize, String)> = vec![];
+    for id in ["revisions", "not", "all", "branches", "tags"] {
+        let (Some(values), Some(indices)) =
+            (matches.get_many::<String>(id), matches.indices_of(id))
+        else {
+            continue;
+        };
+        for (value, index) in values.zip(indices) {
+            let arg = match id {
+                "branches" | "tags" => format!("--{}={}", id, value),
+                _ => value.clone(),
+            };
+            args.push((index, arg));
+        }
+    }
+    args.sort();
+    args.into_iter().map(|(_, arg)| arg).collect()
+}
@@ -0,0 +1,279 @@
+// Reading and parsing of objects stored under .rgit/objects
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::bufread::ZlibDecoder;
+
+use crate::trailer::{parse_trailers, Trailer};
+
+// Read an object from the object store and return its type with the raw content
+// Blobs written by `add` are stored without a header, so they are reported as "blob"
+pub fn read_object(hash: &str) -> io::Result<(String, Vec<u8>)> {
+    if hash.len() < 3 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid object name '{}'", hash),
+        ));
+    }
+
+    let object_path = format!(".rgit/objects/{}/{}", &hash[0..2], &hash[2..]);
+    if !Path::new(&object_path).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Object '{}' not found", hash),
+        ));
+    }
+
+    // Read the object file as raw bytes
+    let mut object_file = File::open(&object_path)?;
+    let mut encoded_object = Vec::new();
+    object_file.read_to_end(&mut encoded_object)?;
+
+    // Decompress the object using ZlibDecoder
+    let mut d = ZlibDecoder::new(&encoded_object[..]);
+    let mut contents = Vec::new();
+    d.read_to_end(&mut contents)?;
+
+    // Split "<type> <size>\0" header from the content
+    if let Some(null_pos) = contents.iter().position(|&b| b == 0) {
+        if let Ok(header) = std::str::from_utf8(&contents[..null_pos]) {
+            if let Some((kind, size)) = header.split_once(' ') {
+                if matches!(kind, "commit" | "tree" | "tag" | "blob")
+                    && size.parse::<usize>() == Ok(contents.len() - null_pos - 1)
+                {
+                    return Ok((kind.to_string(), contents[null_pos + 1..].to_vec()));
+                }
+            }
+        }
+    }
+
+    Ok(("blob".to_string(), contents))
+}
+
+// Read an object and make sure it has the expected type
+pub fn read_object_as(hash: &str, expected: &str) -> io::Result<String> {
+    let (kind, contents) = read_object(hash)?;
+    if kind != expected {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Object '{}' is a {}, not a {}", hash, kind, expected),
+        ));
+    }
+
+    String::from_utf8(contents).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} content is not valid UTF-8", expected),
+        )
+    })
+}
+
+// Read a tree object ("<mode> <type> <hash>\t<path>" lines) as a map of file path to blob hash
+// Later entries for the same path replace earlier ones, like the index they were built from
+pub fn read_tree(hash: &str) -> io::Result<BTreeMap<String, String>> {
+    let contents = read_object_as(hash, "tree")?;
+    let mut entries = BTreeMap::new();
+
+    for line in contents.lines() {
+        let Some((info, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let parts: Vec<&str> = info.split_whitespace().collect();
+        if parts.len() != 3 {
+            continue;
+        }
+        entries.insert(path.to_string(), parts[2].to_string());
+    }
+
+    Ok(entries)
+}
+
+// Author or committer identity: "<name> <<email>> <timestamp> <timezone>"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub timezone: String,
+}
+
+impl Identity {
+    pub fn parse(value: &str) -> Option<Identity> {
+        let email_start = value.find('<')?;
+        let email_end = value[email_start..].find('>')? + email_start;
+
+        let name = value[..email_start].trim().to_string();
+        let email = value[email_start + 1..email_end].to_string();
+
+        let mut rest = value[email_end + 1..].split_whitespace();
+        let timestamp = rest.next().and_then(|t| t.parse().ok()).unwrap_or(0);
+        let timezone = rest.next().unwrap_or("+0000").to_string();
+
+        Some(Identity {
+            name,
+            email,
+            timestamp,
+            timezone,
+        })
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} <{}> {} {}",
+            self.name, self.email, self.timestamp, self.timezone
+        )
+    }
+}
+
+// Parsed commit object
+// Commit layout is a list of "Key: value" headers followed by "Message: <message>",
+// where the message runs until the end of the object and may span multiple lines
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Identity,
+    // Commits created before the "Committer" header existed use the author
+    pub committer: Identity,
+    pub message: String,
+}
+
+impl Commit {
+    pub fn parse(contents: &str) -> io::Result<Commit> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut tree = None;
+        let mut parents = vec![];
+        let mut author = None;
+        let mut committer = None;
+        let mut message = String::new();
+
+        let mut rest = contents;
+        while !rest.is_empty() {
+            if let Some(msg) = rest.strip_prefix("Message: ") {
+                message = msg.to_string();
+                break;
+            }
+
+            let (line, remaining) = rest.split_once('\n').unwrap_or((rest, ""));
+            rest = remaining;
+
+            match line.split_once(": ") {
+                Some(("Tree", value)) => tree = Some(value.to_string()),
+                Some(("Parent", value)) => parents.push(value.to_string()),
+                Some(("Author", value)) => author = Identity::parse(value),
+                Some(("Committer", value)) => committer = Identity::parse(value),
+                _ => {}
+            }
+        }
+
+        let author: Identity = author.ok_or_else(|| invalid("Commit is missing its author"))?;
+        Ok(Commit {
+            tree: tree.ok_or_else(|| invalid("Commit is missing its tree"))?,
+            parents,
+            committer: committer.unwrap_or_else(|| author.clone()),
+            author,
+            message,
+        })
+    }
+
+    // Load and parse the commit object with given hash
+    pub fn read(hash: &str) -> io::Result<Commit> {
+        Commit::parse(&read_object_as(hash, "commit")?)
+    }
+
+    // First line of the commit message
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+
+    // Commit message without the subject line and the blank line that follows it
+    pub fn body(&self) -> &str {
+        match self.message.split_once('\n') {
+            Some((_, body)) => body.trim_start_matches('\n'),
+            None => "",
+        }
+    }
+
+    // Trailers at the end of the commit message, e.g. "Signed-off-by: ..."
+    pub fn trailers(&self) -> Vec<Trailer> {
+        parse_trailers(&self.message)
+    }
+}
+
+// Parsed annotated tag object
+// Tag layout is "Object", "Type", "Tag" and "Tagger" headers followed by "Message: <message>"
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub object: String,
+    pub kind: String,
+    pub name: String,
+    pub tagger: Option<Identity>,
+    pub message: String,
+}
+
+impl Tag {
+    pub fn parse(contents: &str) -> io::Result<Tag> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut object = None;
+        let mut kind = None;
+        let mut name = None;
+        let mut tagger = None;
+        let mut message = String::new();
+
+        let mut rest = contents;
+        while !rest.is_empty() {
+            if let Some(msg) = rest.strip_prefix("Message: ") {
+                message = msg.to_string();
+                break;
+            }
+
+            let (line, remaining) = rest.split_once('\n').unwrap_or((rest, ""));
+            rest = remaining;
+
+            match line.split_once(": ") {
+                Some(("Object", value)) => object = Some(value.to_string()),
+                Some(("Type", value)) => kind = Some(value.to_string()),
+                Some(("Tag", value)) => name = Some(value.to_string()),
+                Some(("Tagger", value)) => tagger = Identity::parse(value),
+                _ => {}
+            }
+        }
+
+        Ok(Tag {
+            object: object.ok_or_else(|| invalid("Tag is missing its object"))?,
+            kind: kind.unwrap_or_else(|| "commit".to_string()),
+            name: name.ok_or_else(|| invalid("Tag is missing its name"))?,
+            tagger,
+            message,
+        })
+    }
+}
+
+// Follow annotated tags until a commit is reached
+pub fn peel_to_commit(hash: &str) -> io::Result<String> {
+    let mut current = hash.to_string();
+    loop {
+        let (kind, contents) = read_object(&current)?;
+        match kind.as_str() {
+            "commit" => return Ok(current),
+            "tag" => current = Tag::parse(&String::from_utf8_lossy(&contents))?.object,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Object '{}' is a {}, not a commit", current, kind),
+                ))
+            }
+        }
+    }
+}
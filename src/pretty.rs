@@ -0,0 +1,671 @@
+// Commit formatting for `log --pretty=<format>`, `--oneline` and `--format=<string>`
+
+use std::{
+    collections::HashMap,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::{
+    head::Head,
+    mailmap::Mailmap,
+    object::{peel_to_commit, read_object_as, Commit, Identity},
+    refs::list_refs,
+    trailer::format_trailers,
+};
+
+// Built-in pretty formats and user format strings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrettyFormat {
+    Oneline,
+    Short,
+    Medium,
+    Full,
+    Fuller,
+    Raw,
+    Format(String),
+}
+
+impl PrettyFormat {
+    // Parse a `--pretty` value: a built-in name, "format:<string>", "tformat:<string>",
+    // or a string with placeholders
+    pub fn parse(value: &str) -> io::Result<PrettyFormat> {
+        match value {
+            "oneline" => Ok(PrettyFormat::Oneline),
+            "short" => Ok(PrettyFormat::Short),
+            "medium" => Ok(PrettyFormat::Medium),
+            "full" => Ok(PrettyFormat::Full),
+            "fuller" => Ok(PrettyFormat::Fuller),
+            "raw" => Ok(PrettyFormat::Raw),
+            _ => {
+                if let Some(format) = value
+                    .strip_prefix("format:")
+                    .or_else(|| value.strip_prefix("tformat:"))
+                {
+                    Ok(PrettyFormat::Format(format.to_string()))
+                } else if value.contains('%') {
+                    Ok(PrettyFormat::Format(value.to_string()))
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid pretty format: {}", value),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+// How dates are shown (`--date=<mode>`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateMode {
+    #[default]
+    Default,
+    Relative,
+    Local,
+    Iso,
+    IsoStrict,
+    Rfc,
+    Short,
+    Raw,
+    Unix,
+}
+
+impl DateMode {
+    pub fn parse(value: &str) -> io::Result<DateMode> {
+        match value {
+            "default" => Ok(DateMode::Default),
+            "relative" => Ok(DateMode::Relative),
+            "local" => Ok(DateMode::Local),
+            "iso" | "iso8601" => Ok(DateMode::Iso),
+            "iso-strict" | "iso8601-strict" => Ok(DateMode::IsoStrict),
+            "rfc" | "rfc2822" => Ok(DateMode::Rfc),
+            "short" => Ok(DateMode::Short),
+            "raw" => Ok(DateMode::Raw),
+            "unix" => Ok(DateMode::Unix),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown date format: {}", value),
+            )),
+        }
+    }
+}
+
+// Parse "+0530" style timezone into a fixed offset
+fn parse_timezone(timezone: &str) -> FixedOffset {
+    let sign = if timezone.starts_with('-') { -1 } else { 1 };
+    let digits = timezone.trim_start_matches(['+', '-']);
+    let hours: i32 = digits.get(0..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+    let minutes: i32 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .unwrap_or(FixedOffset::east_opt(0).unwrap())
+}
+
+fn relative_date(timestamp: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(timestamp);
+    let diff = now - timestamp;
+    if diff < 0 {
+        return "in the future".to_string();
+    }
+
+    let plural =
+        |n: i64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+    match diff {
+        d if d < 90 => plural(d, "second"),
+        d if d < 90 * 60 => plural((d + 30) / 60, "minute"),
+        d if d < 36 * 3600 => plural((d + 1800) / 3600, "hour"),
+        d if d < 14 * 86400 => plural((d + 43200) / 86400, "day"),
+        d if d < 70 * 86400 => plural((d + 302400) / 604800, "week"),
+        d if d < 365 * 86400 => plural((d + 1296000) / 2592000, "month"),
+        d => plural(d / 31536000, "year"),
+    }
+}
+
+// Format the date of an identity in the given mode
+pub fn format_date(identity: &Identity, mode: DateMode) -> String {
+    let offset = parse_timezone(&identity.timezone);
+    let utc = Utc
+        .timestamp_opt(identity.timestamp, 0)
+        .single()
+        .unwrap_or_default();
+    let date: DateTime<FixedOffset> = utc.with_timezone(&offset);
+
+    match mode {
+        DateMode::Default => date.format("%a %b %-d %H:%M:%S %Y %z").to_string(),
+        DateMode::Relative => relative_date(identity.timestamp),
+        DateMode::Local => utc
+            .with_timezone(&Local)
+            .format("%a %b %-d %H:%M:%S %Y")
+            .to_string(),
+        DateMode::Iso => date.format("%Y-%m-%d %H:%M:%S %z").to_string(),
+        DateMode::IsoStrict => date.to_rfc3339(),
+        DateMode::Rfc => date.format("%a, %-d %b %Y %H:%M:%S %z").to_string(),
+        DateMode::Short => date.format("%Y-%m-%d").to_string(),
+        DateMode::Raw => format!("{} {}", identity.timestamp, identity.timezone),
+        DateMode::Unix => identity.timestamp.to_string(),
+    }
+}
+
+// Parse a date given on the command line into a unix timestamp
+// Accepts unix timestamps, "now", "yesterday", "<n> <unit>(s) ago", "YYYY-MM-DD",
+// "YYYY-MM-DD HH:MM[:SS]", ISO 8601 and RFC 2822 dates
+pub fn parse_date(value: &str) -> io::Result<i64> {
+    let value = value.trim();
+    let now = Utc::now().timestamp();
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid date '{}'", value),
+        )
+    };
+
+    if let Ok(timestamp) = value.strip_prefix('@').unwrap_or(value).parse::<i64>() {
+        return Ok(timestamp);
+    }
+    match value {
+        "now" => return Ok(now),
+        "yesterday" => return Ok(now - 86400),
+        _ => {}
+    }
+
+    // "2 weeks ago", "3.days.ago"
+    let words: Vec<&str> = value.split(['.', ' ']).filter(|w| !w.is_empty()).collect();
+    if words.len() == 3 && words[2] == "ago" {
+        let count: i64 = words[0].parse().map_err(|_| invalid())?;
+        let unit = match words[1].trim_end_matches('s') {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 3600,
+            "day" => 86400,
+            "week" => 7 * 86400,
+            "month" => 30 * 86400,
+            "year" => 365 * 86400,
+            _ => return Err(invalid()),
+        };
+        // A count too large for a timestamp is not a date
+        return count
+            .checked_mul(unit)
+            .and_then(|offset| now.checked_sub(offset))
+            .ok_or_else(invalid);
+    }
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.timestamp());
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Ok(date.timestamp());
+    }
+    if let Ok(date) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z") {
+        return Ok(date.timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Local
+                .from_local_datetime(&date)
+                .earliest()
+                .map(|d| d.timestamp())
+                .ok_or_else(invalid);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .map(|d| d.timestamp())
+            .ok_or_else(invalid);
+    }
+
+    Err(invalid())
+}
+
+// Ref names pointing to each commit, e.g. "HEAD -> master", "tag: v1.0"
+pub type Decorations = HashMap<String, Vec<String>>;
+
+// Collect the branches and tags pointing to commits, along with HEAD
+// Full names ("refs/heads/master") are used instead of short names when `full_names` is set
+pub fn load_decorations(full_names: bool) -> io::Result<Decorations> {
+    let mut decorations: Decorations = HashMap::new();
+
+    let head = Head::read()?;
+    let current_branch = head.branch();
+    if let Head::Detached(commit) = &head {
+        decorations
+            .entry(commit.clone())
+            .or_default()
+            .push("HEAD".to_string());
+    }
+
+    for (prefix, dir) in [("", "refs/heads/"), ("tag: ", "refs/tags/")] {
+        let name_prefix = if full_names { dir } else { "" };
+
+        for (refname, target) in list_refs(dir)? {
+            let name = &refname[dir.len()..];
+            let Ok(commit_hash) = peel_to_commit(&target) else {
+                continue;
+            };
+            let label = if prefix.is_empty() && current_branch == Some(name) {
+                format!("HEAD -> {}{}", name_prefix, name)
+            } else {
+                format!("{}{}{}", prefix, name_prefix, name)
+            };
+
+            let entry = decorations.entry(commit_hash).or_default();
+            // The current branch always comes first
+            if label.starts_with("HEAD -> ") {
+                entry.insert(0, label);
+            } else {
+                entry.push(label);
+            }
+        }
+    }
+
+    Ok(decorations)
+}
+
+// Settings shared by every formatted commit
+#[derive(Debug, Clone, Default)]
+pub struct FormatContext {
+    pub date_mode: DateMode,
+    pub decorations: Decorations,
+    // Show abbreviated hashes in the built-in formats
+    pub abbrev_commit: bool,
+    // Show ref names next to commits in the built-in formats
+    pub decorate: bool,
+    // Canonical identities for %aN, %aE, %cN, %cE
+    pub mailmap: Mailmap,
+    // Show canonical identities in the built-in formats
+    pub use_mailmap: bool,
+}
+
+impl FormatContext {
+    fn decoration(&self, hash: &str) -> Option<String> {
+        self.decorations
+            .get(hash)
+            .filter(|names| !names.is_empty())
+            .map(|names| names.join(", "))
+    }
+}
+
+fn abbrev(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+// Subject turned into a file name friendly string (used by %f)
+fn sanitize_subject(subject: &str) -> String {
+    let mut sanitized = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            sanitized.push(c);
+        } else if !sanitized.ends_with('-') {
+            sanitized.push('-');
+        }
+    }
+    sanitized.trim_matches(['-', '.']).to_string()
+}
+
+fn color_code(name: &str) -> Option<&'static str> {
+    match name {
+        "red" => Some("\x1b[31m"),
+        "green" => Some("\x1b[32m"),
+        "yellow" => Some("\x1b[33m"),
+        "blue" => Some("\x1b[34m"),
+        "magenta" => Some("\x1b[35m"),
+        "cyan" => Some("\x1b[36m"),
+        "white" => Some("\x1b[37m"),
+        "bold" => Some("\x1b[1m"),
+        "reset" => Some("\x1b[m"),
+        _ => None,
+    }
+}
+
+// Expand the second letter of an identity placeholder (%an, %ae, %ad, ...)
+fn expand_identity(
+    identity: &Identity,
+    placeholder: char,
+    context: &FormatContext,
+) -> Option<String> {
+    match placeholder {
+        'n' => Some(identity.name.clone()),
+        'e' => Some(identity.email.clone()),
+        'l' => Some(identity.email.split('@').next().unwrap_or("").to_string()),
+        'N' => Some(context.mailmap.lookup(&identity.name, &identity.email).0),
+        'E' => Some(context.mailmap.lookup(&identity.name, &identity.email).1),
+        'L' => {
+            let (_, email) = context.mailmap.lookup(&identity.name, &identity.email);
+            Some(email.split('@').next().unwrap_or("").to_string())
+        }
+        'd' => Some(format_date(identity, context.date_mode)),
+        'D' => Some(format_date(identity, DateMode::Rfc)),
+        'r' => Some(format_date(identity, DateMode::Relative)),
+        't' => Some(format_date(identity, DateMode::Unix)),
+        'i' => Some(format_date(identity, DateMode::Iso)),
+        'I' => Some(format_date(identity, DateMode::IsoStrict)),
+        's' => Some(format_date(identity, DateMode::Short)),
+        _ => None,
+    }
+}
+
+// Expand the placeholders of a format string for the given commit
+// Supported: %H %h %T %t %P %p, %an %ae %al %aN %aE %aL %ad %aD %ar %at %ai %aI %as (and %c.. for the committer),
+// %s %f %b %B %d %D %n %% %xNN, %Cred %Cgreen %Cblue %Creset %C(<color>) and %(trailers[:options])
+pub fn format_commit(format: &str, hash: &str, commit: &Commit, context: &FormatContext) -> String {
+    let mut output = String::new();
+    let mut rest = format;
+
+    while let Some(pos) = rest.find('%') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        // "%(name:options)" placeholders
+        if let Some(inner) = rest.strip_prefix('(') {
+            if let Some(end) = inner.find(')') {
+                let (name, options) = inner[..end].split_once(':').unwrap_or((&inner[..end], ""));
+                if name == "trailers" {
+                    output.push_str(&format_trailers(&commit.trailers(), options));
+                    rest = &inner[end + 1..];
+                    continue;
+                }
+            }
+            output.push('%');
+            continue;
+        }
+
+        // "%C<color>" and "%C(<color>)" placeholders
+        if let Some(color) = rest.strip_prefix('C') {
+            let parsed = if let Some(inner) = color.strip_prefix('(') {
+                inner
+                    .find(')')
+                    .and_then(|end| color_code(&inner[..end]).map(|c| (c, &inner[end + 1..])))
+            } else {
+                ["red", "green", "blue", "reset"]
+                    .iter()
+                    .find(|name| color.starts_with(*name))
+                    .and_then(|name| color_code(name).map(|c| (c, &color[name.len()..])))
+            };
+            if let Some((code, remaining)) = parsed {
+                output.push_str(code);
+                rest = remaining;
+                continue;
+            }
+        }
+
+        // "%xNN" hex escapes
+        if let Some(hex) = rest.strip_prefix('x') {
+            if let Some(byte) = hex.get(..2).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                output.push(byte as char);
+                rest = &hex[2..];
+                continue;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let expanded = match chars.next() {
+            Some('H') => Some(hash.to_string()),
+            Some('h') => Some(abbrev(hash).to_string()),
+            Some('T') => Some(commit.tree.clone()),
+            Some('t') => Some(abbrev(&commit.tree).to_string()),
+            Some('P') => Some(commit.parents.join(" ")),
+            Some('p') => Some(
+                commit
+                    .parents
+                    .iter()
+                    .map(|p| abbrev(p))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Some(kind @ ('a' | 'c')) => {
+                let identity = if kind == 'a' {
+                    &commit.author
+                } else {
+                    &commit.committer
+                };
+                chars
+                    .next()
+                    .and_then(|p| expand_identity(identity, p, context))
+            }
+            Some('s') => Some(commit.subject().to_string()),
+            Some('f') => Some(sanitize_subject(commit.subject())),
+            Some('b') => Some(commit.body().to_string()),
+            Some('B') => Some(commit.message.clone()),
+            Some('d') => Some(
+                context
+                    .decoration(hash)
+                    .map(|d| format!(" ({})", d))
+                    .unwrap_or_default(),
+            ),
+            Some('D') => Some(context.decoration(hash).unwrap_or_default()),
+            Some('n') => Some("\n".to_string()),
+            Some('%') => Some("%".to_string()),
+            _ => None,
+        };
+
+        match expanded {
+            Some(value) => {
+                output.push_str(&value);
+                rest = chars.as_str();
+            }
+            // Unknown placeholders are printed as they are
+            None => output.push('%'),
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+// Commit message indented by four spaces, as shown by the built-in formats
+fn indented_message(message: &str) -> String {
+    message
+        .trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("    {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Format a commit with a built-in pretty format or a format string
+// The result has no trailing newline
+pub fn pretty_commit(
+    pretty: &PrettyFormat,
+    hash: &str,
+    commit: &Commit,
+    context: &FormatContext,
+) -> io::Result<String> {
+    let merge_line = if commit.parents.len() > 1 {
+        let short_parents: Vec<&str> = commit.parents.iter().map(|p| abbrev(p)).collect();
+        Some(format!("Merge: {}", short_parents.join(" ")))
+    } else {
+        None
+    };
+    let person = |identity: &Identity| {
+        let (name, email) = if context.use_mailmap {
+            context.mailmap.lookup(&identity.name, &identity.email)
+        } else {
+            (identity.name.clone(), identity.email.clone())
+        };
+        format!("{} <{}>", name, email)
+    };
+    let shown_hash = if context.abbrev_commit {
+        abbrev(hash)
+    } else {
+        hash
+    };
+    let shown_hash = match context.decoration(hash).filter(|_| context.decorate) {
+        Some(decoration) => format!("{} ({})", shown_hash, decoration),
+        None => shown_hash.to_string(),
+    };
+
+    let mut lines: Vec<String> = vec![];
+    match pretty {
+        PrettyFormat::Format(format) => return Ok(format_commit(format, hash, commit, context)),
+        PrettyFormat::Oneline => return Ok(format!("{} {}", shown_hash, commit.subject())),
+        PrettyFormat::Raw => {
+            lines.push(format!("commit {}", shown_hash));
+            lines.push(read_object_as(hash, "commit")?.trim_end().to_string());
+            return Ok(lines.join("\n"));
+        }
+        PrettyFormat::Short => {
+            lines.push(format!("commit {}", shown_hash));
+            lines.extend(merge_line);
+            lines.push(format!("Author: {}", person(&commit.author)));
+            lines.push(String::new());
+            lines.push(indented_message(commit.subject()));
+        }
+        PrettyFormat::Medium => {
+            lines.push(format!("commit {}", shown_hash));
+            lines.extend(merge_line);
+            lines.push(format!("Author: {}", person(&commit.author)));
+            lines.push(format!(
+                "Date:   {}",
+                format_date(&commit.author, context.date_mode)
+            ));
+            lines.push(String::new());
+            lines.push(indented_message(&commit.message));
+        }
+        PrettyFormat::Full => {
+            lines.push(format!("commit {}", shown_hash));
+            lines.extend(merge_line);
+            lines.push(format!("Author: {}", person(&commit.author)));
+            lines.push(format!("Commit: {}", person(&commit.committer)));
+            lines.push(String::new());
+            lines.push(indented_message(&commit.message));
+        }
+        PrettyFormat::Fuller => {
+            lines.push(format!("commit {}", shown_hash));
+            lines.extend(merge_line);
+            lines.push(format!("Author:     {}", person(&commit.author)));
+            lines.push(format!(
+                "AuthorDate: {}",
+                format_date(&commit.author, context.date_mode)
+            ));
+            lines.push(format!("Commit:     {}", person(&commit.committer)));
+            lines.push(format!(
+                "CommitDate: {}",
+                format_date(&commit.committer, context.date_mode)
+            ));
+            lines.push(String::new());
+            lines.push(indented_message(&commit.message));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+    const PARENT: &str = "89abcdef0123456789abcdef0123456789abcdef";
+
+    fn identity(name: &str, email: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+            timestamp: 1_700_000_000,
+            timezone: "+0200".to_string(),
+        }
+    }
+
+    fn commit() -> Commit {
+        Commit {
+            tree: "fedcba9876543210fedcba9876543210fedcba98".to_string(),
+            parents: vec![PARENT.to_string()],
+            author: identity("Ann", "ann@example.com"),
+            committer: identity("Bob", "bob@example.com"),
+            message:
+                "Fix the parser: (again)\n\nLonger text.\n\nSigned-off-by: Ann <ann@example.com>\n"
+                    .to_string(),
+        }
+    }
+
+    fn expand(format: &str, context: &FormatContext) -> String {
+        format_commit(format, HASH, &commit(), context)
+    }
+
+    #[test]
+    fn hashes_and_parents() {
+        let context = FormatContext::default();
+        assert_eq!(expand("%H", &context), HASH);
+        assert_eq!(expand("%h %t %p", &context), "0123456 fedcba9 89abcde");
+        assert_eq!(expand("%P", &context), PARENT);
+    }
+
+    #[test]
+    fn identities_and_dates() {
+        let context = FormatContext::default();
+        assert_eq!(
+            expand("%an <%ae> %al, %cn <%ce>", &context),
+            "Ann <ann@example.com> ann, Bob <bob@example.com>"
+        );
+        assert_eq!(expand("%at", &context), "1700000000");
+        assert_eq!(expand("%ai", &context), "2023-11-15 00:13:20 +0200");
+        assert_eq!(expand("%as", &context), "2023-11-15");
+        assert_eq!(expand("%aD", &context), "Wed, 15 Nov 2023 00:13:20 +0200");
+
+        let context = FormatContext {
+            date_mode: DateMode::Short,
+            mailmap: Mailmap::parse("Ann Other <other@example.com> <ann@example.com>"),
+            ..FormatContext::default()
+        };
+        assert_eq!(expand("%ad", &context), "2023-11-15");
+        assert_eq!(
+            expand("%aN <%aE> %aL", &context),
+            "Ann Other <other@example.com> other"
+        );
+        assert_eq!(expand("%cN", &context), "Bob");
+    }
+
+    #[test]
+    fn message_parts() {
+        let context = FormatContext::default();
+        assert_eq!(expand("%s", &context), "Fix the parser: (again)");
+        assert_eq!(expand("%f", &context), "Fix-the-parser-again");
+        assert_eq!(
+            expand("%b", &context),
+            "Longer text.\n\nSigned-off-by: Ann <ann@example.com>\n"
+        );
+        assert_eq!(expand("%B", &context), commit().message);
+        assert_eq!(
+            expand("%(trailers:key=signed-off-by,valueonly)", &context),
+            "Ann <ann@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn decorations() {
+        let mut context = FormatContext::default();
+        assert_eq!(expand("[%d][%D]", &context), "[][]");
+        context.decorations.insert(
+            HASH.to_string(),
+            vec!["HEAD -> master".to_string(), "tag: v1".to_string()],
+        );
+        assert_eq!(
+            expand("[%d][%D]", &context),
+            "[ (HEAD -> master, tag: v1)][HEAD -> master, tag: v1]"
+        );
+    }
+
+    #[test]
+    fn escapes_colors_and_unknown_placeholders() {
+        let context = FormatContext::default();
+        assert_eq!(expand("a%nb%%c%x41", &context), "a\nb%cA");
+        assert_eq!(
+            expand("%Cred%h%Creset %C(bold)x", &context),
+            "\x1b[31m0123456\x1b[m \x1b[1mx"
+        );
+        assert_eq!(expand("%Z %(unknown) %x4", &context), "%Z %(unknown) %x4");
+        assert_eq!(expand("trailing %", &context), "trailing %");
+    }
+}
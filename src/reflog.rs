@@ -0,0 +1,418 @@
+// Reflogs: every update of HEAD and of the branches is recorded in .rgit/logs/<refname>,
+// oldest first, one line per update in Git's format:
+// "<old> <new> <name> <<email>> <timestamp> <tz>\t<message>"
+// They answer "<ref>@{<n>}" (the n-th previous value) and "<ref>@{<date>}" revisions.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::get_config,
+    head::Head,
+    hooks::NULL_HASH,
+    lockfile::write_locked,
+    object::{peel_to_commit, Identity},
+    pretty::{format_date, parse_date, DateMode},
+    refs::read_ref,
+    revparse::{expand_ref_name, unique_abbrev},
+    revwalk::ancestors,
+};
+
+// Entries older than these are removed by `reflog expire`, unless gc.reflogExpire and
+// gc.reflogExpireUnreachable say otherwise
+const DEFAULT_EXPIRE: &str = "90.days.ago";
+const DEFAULT_EXPIRE_UNREACHABLE: &str = "30.days.ago";
+
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub identity: Identity,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    fn parse(line: &str) -> Option<ReflogEntry> {
+        let (update, message) = line.split_once('\t').unwrap_or((line, ""));
+        let mut parts = update.splitn(3, ' ');
+        let old = parts.next()?.to_string();
+        let new = parts.next()?.to_string();
+        let identity = Identity::parse(parts.next()?)?;
+        Some(ReflogEntry {
+            old,
+            new,
+            identity,
+            message: message.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ReflogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}\t{}",
+            self.old, self.new, self.identity, self.message
+        )
+    }
+}
+
+fn reflog_path(refname: &str) -> String {
+    format!(".rgit/logs/{}", refname)
+}
+
+// HEAD, branches, remote-tracking branches and notes are logged (core.logAllRefUpdates
+// set to "always" logs every reference, "false" none), and so is any reference that
+// already has a log
+fn should_log(refname: &str) -> io::Result<bool> {
+    if Path::new(&reflog_path(refname)).is_file() {
+        return Ok(true);
+    }
+    let setting = get_config("[core]", "logAllRefUpdates")?.map(|v| v.to_ascii_lowercase());
+    Ok(match setting.as_deref() {
+        Some("false") => false,
+        Some("always") => true,
+        _ => {
+            refname == "HEAD"
+                || ["refs/heads/", "refs/remotes/", "refs/notes/"]
+                    .iter()
+                    .any(|prefix| refname.starts_with(prefix))
+        }
+    })
+}
+
+// Record an update of a reference from `old` to `new` in its log
+pub fn append_reflog(refname: &str, old: &str, new: &str, message: &str) -> io::Result<()> {
+    if !should_log(refname)? {
+        return Ok(());
+    }
+    let name = get_config("[user]", "name")?.unwrap_or("default".to_string());
+    let email = get_config("[user]", "email")?.unwrap_or("default@email.com".to_string());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?
+        .as_secs();
+
+    let entry = ReflogEntry {
+        old: old.to_string(),
+        new: new.to_string(),
+        identity: Identity {
+            name,
+            email,
+            timestamp: now as i64,
+            timezone: "+0000".to_string(),
+        },
+        // An entry takes one line
+        message: message.split_whitespace().collect::<Vec<_>>().join(" "),
+    };
+
+    let path = reflog_path(refname);
+    if let Some(parent) = Path::new(&path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut log_file = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)?;
+    writeln!(log_file, "{}", entry)
+}
+
+// Entries of a reference's log, oldest first (none when it has no log)
+pub fn read_reflog(refname: &str) -> io::Result<Vec<ReflogEntry>> {
+    match fs::read_to_string(reflog_path(refname)) {
+        Ok(log) => Ok(log.lines().filter_map(ReflogEntry::parse).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_reflog(refname: &str, entries: &[ReflogEntry]) -> io::Result<()> {
+    let contents: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+    write_locked(reflog_path(refname), contents.as_bytes())
+}
+
+// Remove the log of a deleted reference, and the directories it leaves empty
+pub fn delete_reflog(refname: &str) -> io::Result<()> {
+    let path = reflog_path(refname);
+    match fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+    let mut dir = Path::new(&path).parent();
+    while let Some(parent) = dir {
+        if parent == Path::new(".rgit/logs") || fs::remove_dir(parent).is_err() {
+            break;
+        }
+        dir = parent.parent();
+    }
+    Ok(())
+}
+
+// Give the log of a renamed (or copied, with `keep`) reference to its new name
+pub fn move_reflog(from: &str, to: &str, keep: bool) -> io::Result<()> {
+    let (from_path, to_path) = (reflog_path(from), reflog_path(to));
+    if !Path::new(&from_path).is_file() {
+        return delete_reflog(to);
+    }
+    if let Some(parent) = Path::new(&to_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if keep {
+        fs::copy(&from_path, &to_path)?;
+        Ok(())
+    } else {
+        fs::rename(&from_path, &to_path)?;
+        delete_reflog(from)
+    }
+}
+
+// Reference whose log a name stands for: "HEAD", a short or full reference name,
+// or the current branch for an empty name (as in "@{1}")
+pub fn reflog_refname(name: &str) -> io::Result<String> {
+    if name.is_empty() {
+        return Ok(Head::read()?.refname().unwrap_or("HEAD").to_string());
+    }
+    if Path::new(&reflog_path(name)).is_file() {
+        return Ok(name.to_string());
+    }
+    expand_ref_name(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Reflog for '{}' not found", name),
+        )
+    })
+}
+
+// Value of a reference according to its log: "@{<n>}" is the value n updates ago,
+// "@{<date>}" the value it had at that date
+pub fn resolve_reflog(name: &str, selector: &str) -> io::Result<String> {
+    let refname = reflog_refname(name)?;
+    let entries = read_reflog(&refname)?;
+    let Some(oldest) = entries.first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Log for '{}' is empty", refname),
+        ));
+    };
+
+    if let Ok(n) = selector.parse::<usize>() {
+        return entries
+            .iter()
+            .rev()
+            .nth(n)
+            .map(|entry| entry.new.clone())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Log for '{}' only has {} entries",
+                        if name.is_empty() { &refname } else { name },
+                        entries.len()
+                    ),
+                )
+            });
+    }
+
+    let date = parse_date(selector)?;
+    match entries
+        .iter()
+        .rev()
+        .find(|entry| entry.identity.timestamp <= date)
+    {
+        Some(entry) => Ok(entry.new.clone()),
+        // Before the first entry the reference held the old value of that entry
+        None => {
+            eprintln!(
+                "warning: log for '{}' only goes back to {}",
+                refname,
+                format_date(&oldest.identity, DateMode::Default)
+            );
+            Ok(if oldest.old == NULL_HASH {
+                oldest.new.clone()
+            } else {
+                oldest.old.clone()
+            })
+        }
+    }
+}
+
+// `reflog show [<ref>]`: the entries of a log, newest first, as "<hash> <ref>@{<n>}: <message>"
+pub fn reflog_show(name: &str, max_count: Option<usize>) -> io::Result<()> {
+    let refname = reflog_refname(name)?;
+    let mut output = String::new();
+    for (n, entry) in read_reflog(&refname)?
+        .iter()
+        .rev()
+        .enumerate()
+        .take(max_count.unwrap_or(usize::MAX))
+    {
+        output.push_str(&format!(
+            "{} {}@{{{}}}: {}\n",
+            unique_abbrev(&entry.new, 7)?,
+            name,
+            n,
+            entry.message
+        ));
+    }
+    print!("{}", output);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReflogExpireOptions {
+    // Remove entries older than this date (gc.reflogExpire, 90 days by default)
+    pub expire: Option<String>,
+    // Remove entries older than this date whose commit is no longer reachable from
+    // the reference (gc.reflogExpireUnreachable, 30 days by default)
+    pub expire_unreachable: Option<String>,
+    // Expire the logs of every reference
+    pub all: bool,
+    // Only print the entries that would be removed
+    pub dry_run: bool,
+}
+
+// Cutoff of an expiry date: "never" keeps every entry, "all" and "now" remove all of them
+fn expiry_cutoff(value: Option<&str>, key: &str, default: &str) -> io::Result<i64> {
+    let configured = match value {
+        Some(value) => value.to_string(),
+        None => get_config("[gc]", key)?.unwrap_or(default.to_string()),
+    };
+    match configured.as_str() {
+        "never" | "false" => Ok(i64::MIN),
+        "all" | "now" => Ok(i64::MAX),
+        date => parse_date(date),
+    }
+}
+
+// Every reference that has a log, e.g. "HEAD" and "refs/heads/master"
+fn list_reflogs() -> io::Result<Vec<String>> {
+    fn collect(dir: &Path, prefix: &str, names: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if entry.path().is_dir() {
+                collect(&entry.path(), &format!("{}/", name), names)?;
+            } else if !name.ends_with(".lock") {
+                names.push(name);
+            }
+        }
+        Ok(())
+    }
+
+    let mut names = vec![];
+    if Path::new(".rgit/logs").is_dir() {
+        collect(Path::new(".rgit/logs"), "", &mut names)?;
+    }
+    names.sort();
+    Ok(names)
+}
+
+// `reflog expire`: remove old entries from the logs of the given references (or all of them)
+pub fn reflog_expire(names: &[String], options: &ReflogExpireOptions) -> io::Result<()> {
+    let refnames = if options.all {
+        list_reflogs()?
+    } else if names.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No reflog specified, name references or use --all",
+        ));
+    } else {
+        names
+            .iter()
+            .map(|name| reflog_refname(name))
+            .collect::<io::Result<Vec<_>>>()?
+    };
+
+    let expire = expiry_cutoff(options.expire.as_deref(), "reflogExpire", DEFAULT_EXPIRE)?;
+    let expire_unreachable = expiry_cutoff(
+        options.expire_unreachable.as_deref(),
+        "reflogExpireUnreachable",
+        DEFAULT_EXPIRE_UNREACHABLE,
+    )?;
+
+    for refname in refnames {
+        let entries = read_reflog(&refname)?;
+        // Commits still reachable from the reference are kept longer
+        let reachable = if entries
+            .iter()
+            .any(|entry| entry.identity.timestamp <= expire_unreachable)
+        {
+            match read_ref(&refname)?.map(|tip| peel_to_commit(&tip)) {
+                Some(Ok(tip)) => ancestors(&[tip])?,
+                _ => HashSet::new(),
+            }
+        } else {
+            HashSet::new()
+        };
+
+        let total = entries.len();
+        let mut kept = vec![];
+        for (i, entry) in entries.into_iter().enumerate() {
+            let timestamp = entry.identity.timestamp;
+            let expired = timestamp <= expire
+                || (timestamp <= expire_unreachable && !reachable.contains(&entry.new));
+            if !expired {
+                kept.push(entry);
+            } else if options.dry_run {
+                println!(
+                    "would prune {}@{{{}}}: {}",
+                    refname,
+                    total - 1 - i,
+                    entry.message
+                );
+            }
+        }
+        if !options.dry_run && kept.len() < total {
+            write_reflog(&refname, &kept)?;
+        }
+    }
+    Ok(())
+}
+
+// `reflog delete <ref>@{<n>}...`: remove single entries from logs
+pub fn reflog_delete(specs: &[String]) -> io::Result<()> {
+    // Entry numbers count from the newest entry of the log before any deletion
+    let mut deletions: BTreeMap<String, HashSet<usize>> = BTreeMap::new();
+    for spec in specs {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a reflog entry: '{}' (expected <ref>@{{<n>}})", spec),
+            )
+        };
+        let (name, selector) = spec
+            .strip_suffix('}')
+            .and_then(|spec| spec.rsplit_once("@{"))
+            .ok_or_else(invalid)?;
+        let n: usize = selector.parse().map_err(|_| invalid())?;
+        deletions
+            .entry(reflog_refname(name)?)
+            .or_default()
+            .insert(n);
+    }
+
+    for (refname, numbers) in deletions {
+        let entries = read_reflog(&refname)?;
+        let total = entries.len();
+        if let Some(missing) = numbers.iter().find(|&&n| n >= total) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Reflog entry {}@{{{}}} not found", refname, missing),
+            ));
+        }
+        let kept: Vec<ReflogEntry> = entries
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !numbers.contains(&(total - 1 - i)))
+            .map(|(_, entry)| entry)
+            .collect();
+        write_reflog(&refname, &kept)?;
+    }
+    Ok(())
+}
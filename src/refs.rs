@@ -0,0 +1,557 @@
+// References: files under .rgit/ holding an object hash, or "ref: <refname>" for a
+// symbolic reference (e.g. HEAD pointing to refs/heads/master)
+
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use crate::{
+    hooks::NULL_HASH,
+    lockfile::LockFile,
+    object::{read_object, Tag},
+    reflog::{append_reflog, delete_reflog},
+    revparse::{check_ref_format, resolve_revision, shorten_ref_name},
+};
+
+// Symbolic references followed before giving up, as in Git
+const MAX_SYMREF_DEPTH: usize = 5;
+
+// Contents of a reference file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefValue {
+    Direct(String),
+    Symbolic(String),
+}
+
+// Contents of a reference without following symbolic references, None when it does not exist
+pub fn read_raw_ref(refname: &str) -> io::Result<Option<RefValue>> {
+    let path = format!(".rgit/{}", refname);
+    if !Path::new(&path).is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    let content = content.trim();
+    Ok(match content.strip_prefix("ref:") {
+        Some(target) => Some(RefValue::Symbolic(target.trim().to_string())),
+        None if content.is_empty() => None,
+        None => Some(RefValue::Direct(content.to_string())),
+    })
+}
+
+// Name of the reference at the end of a chain of symbolic references (the name itself when direct)
+pub fn resolve_symbolic(refname: &str) -> io::Result<String> {
+    let mut name = refname.to_string();
+    for _ in 0..MAX_SYMREF_DEPTH {
+        match read_raw_ref(&name)? {
+            Some(RefValue::Symbolic(target)) => name = target,
+            _ => return Ok(name),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Too many levels of symbolic references at '{}'", refname),
+    ))
+}
+
+// Object hash a reference points to, following symbolic references ("ref: refs/heads/main")
+pub fn read_ref(refname: &str) -> io::Result<Option<String>> {
+    match read_raw_ref(&resolve_symbolic(refname)?)? {
+        Some(RefValue::Direct(hash)) => Ok(Some(hash)),
+        _ => Ok(None),
+    }
+}
+
+// Point a reference at another one, e.g. HEAD at refs/heads/master. With a message, the
+// move from the old to the new commit is recorded in the reference's log.
+pub fn write_symbolic_ref(refname: &str, target: &str, message: Option<&str>) -> io::Result<()> {
+    let mut lock =
+        LockFile::acquire(format!(".rgit/{}", refname)).map_err(|e| lock_error(refname, e))?;
+    let old = read_ref(refname)?;
+    lock.write_all(format!("ref: {}", target).as_bytes())?;
+    lock.commit()?;
+    // Only a reference that really moved is logged
+    match (message, read_ref(target)?) {
+        (Some(message), Some(new)) => {
+            append_reflog(refname, old.as_deref().unwrap_or(NULL_HASH), &new, message)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Point a reference at an object. When `old` is given the reference has to hold that
+// hash first (the null hash for a reference that must not exist yet). With `deref`,
+// symbolic references are followed and the reference they end at is updated.
+// The message is recorded in the reflog.
+pub fn write_ref(
+    refname: &str,
+    new: &str,
+    old: Option<&str>,
+    deref: bool,
+    message: &str,
+) -> io::Result<()> {
+    let mut transaction = RefTransaction::new(message);
+    transaction.update(refname, new, old, deref);
+    transaction.commit()
+}
+
+// Remove a reference, checking its old value like `write_ref`
+pub fn delete_ref(refname: &str, old: Option<&str>, deref: bool) -> io::Result<()> {
+    let mut transaction = RefTransaction::default();
+    transaction.delete(refname, old, deref);
+    transaction.commit()
+}
+
+#[derive(Debug, Clone)]
+enum RefChange {
+    Write(String),
+    Delete,
+    // Only check the old value
+    Verify,
+}
+
+#[derive(Debug, Clone)]
+struct RefUpdate {
+    refname: String,
+    change: RefChange,
+    old: Option<String>,
+    deref: bool,
+}
+
+// Changes of several references applied together or not at all: every reference is
+// locked and checked against its expected old value before any of them changes
+#[derive(Debug, Clone, Default)]
+pub struct RefTransaction {
+    updates: Vec<RefUpdate>,
+    // Reason recorded in the reflogs of the updated references
+    message: String,
+}
+
+impl RefTransaction {
+    pub fn new(message: &str) -> RefTransaction {
+        RefTransaction {
+            updates: vec![],
+            message: message.to_string(),
+        }
+    }
+
+    pub fn update(&mut self, refname: &str, new: &str, old: Option<&str>, deref: bool) {
+        self.push(refname, RefChange::Write(new.to_string()), old, deref);
+    }
+
+    pub fn delete(&mut self, refname: &str, old: Option<&str>, deref: bool) {
+        self.push(refname, RefChange::Delete, old, deref);
+    }
+
+    pub fn verify(&mut self, refname: &str, old: Option<&str>) {
+        self.push(refname, RefChange::Verify, old, true);
+    }
+
+    fn push(&mut self, refname: &str, change: RefChange, old: Option<&str>, deref: bool) {
+        self.updates.push(RefUpdate {
+            refname: refname.to_string(),
+            change,
+            old: old.map(|o| o.to_string()),
+            deref,
+        });
+    }
+
+    pub fn commit(self) -> io::Result<()> {
+        let mut updates = vec![];
+        for update in self.updates {
+            let name = if update.deref {
+                resolve_symbolic(&update.refname)?
+            } else {
+                update.refname.clone()
+            };
+            updates.push((name, update));
+        }
+        // Locks are taken in name order so concurrent transactions cannot deadlock
+        updates.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if let Some(pair) = updates.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Multiple updates for ref '{}' not allowed", pair[0].0),
+            ));
+        }
+
+        let mut locked = vec![];
+        for (name, update) in updates {
+            let lock =
+                LockFile::acquire(format!(".rgit/{}", name)).map_err(|e| lock_error(&name, e))?;
+            check_old_value(&name, update.old.as_deref())?;
+            if matches!(update.change, RefChange::Delete) && read_raw_ref(&name)?.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Reference '{}' not found", name),
+                ));
+            }
+            let old = read_ref(&name)?.unwrap_or(NULL_HASH.to_string());
+            locked.push((name, update.change, old, lock));
+        }
+
+        // Every reference is locked and verified, apply the changes
+        let mut applied = vec![];
+        for (name, change, old, mut lock) in locked {
+            match &change {
+                RefChange::Write(new) => {
+                    lock.write_all(new.as_bytes())?;
+                    lock.commit()?;
+                }
+                RefChange::Delete => {
+                    lock.delete()?;
+                    remove_empty_ref_dirs(&name);
+                }
+                RefChange::Verify => continue,
+            }
+            applied.push((name, change, old));
+        }
+
+        // The reflogs only record updates that happened, once all of them did. Updates of
+        // the current branch are also recorded in the HEAD log.
+        let head_target = resolve_symbolic("HEAD")?;
+        for (name, change, old) in applied {
+            match change {
+                RefChange::Write(new) => {
+                    append_reflog(&name, &old, &new, &self.message)?;
+                    if name != "HEAD" && name == head_target {
+                        append_reflog("HEAD", &old, &new, &self.message)?;
+                    }
+                }
+                RefChange::Delete => delete_reflog(&name)?,
+                RefChange::Verify => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn lock_error(refname: &str, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("cannot lock ref '{}': {}", refname, e))
+}
+
+// Remove the directories a deleted reference leaves empty (e.g. refs/heads/feature/),
+// keeping refs/ and namespaces like refs/heads/
+fn remove_empty_ref_dirs(refname: &str) {
+    let path = format!(".rgit/{}", refname);
+    let mut dir = Path::new(&path).parent();
+    while let Some(parent) = dir {
+        let namespace = parent
+            .parent()
+            .is_none_or(|grandparent| grandparent == Path::new(".rgit/refs"));
+        if namespace || fs::remove_dir(parent).is_err() {
+            break;
+        }
+        dir = parent.parent();
+    }
+}
+
+// References below a prefix (e.g. "refs/heads/") with the hashes they point to, sorted by name
+pub fn list_refs(prefix: &str) -> io::Result<Vec<(String, String)>> {
+    fn collect(dir: &Path, refname: &str, refs: &mut Vec<(String, String)>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", refname, entry.file_name().to_string_lossy());
+            // Lock files of references being updated (or left by a crash) are not references
+            if name.ends_with(".lock") {
+                continue;
+            }
+            if entry.path().is_dir() {
+                collect(&entry.path(), &format!("{}/", name), refs)?;
+            } else if let Some(hash) = read_ref(&name)? {
+                refs.push((name, hash));
+            }
+        }
+        Ok(())
+    }
+
+    let mut refs = vec![];
+    let dir = format!(".rgit/{}", prefix);
+    if Path::new(&dir).is_dir() {
+        collect(Path::new(&dir), prefix, &mut refs)?;
+    }
+    refs.sort();
+    Ok(refs)
+}
+
+// Compare-and-swap check of the value a reference holds before it is changed
+fn check_old_value(refname: &str, old: Option<&str>) -> io::Result<()> {
+    let Some(old) = old else {
+        return Ok(());
+    };
+    let current = read_ref(refname)?;
+    let expected = (!old.is_empty() && old != NULL_HASH).then_some(old);
+    if current.as_deref() == expected {
+        return Ok(());
+    }
+
+    let problem = match (current, expected) {
+        (Some(_), None) => "reference already exists".to_string(),
+        (Some(current), Some(expected)) => format!("is at {} but expected {}", current, expected),
+        (None, _) => format!("unable to resolve reference '{}'", refname),
+    };
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("cannot lock ref '{}': {}", refname, problem),
+    ))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UpdateRefOptions {
+    // Delete the reference instead of updating it
+    pub delete: bool,
+    // Update a symbolic reference itself instead of the reference it points to
+    pub no_deref: bool,
+    // Reason recorded in the reflog
+    pub message: String,
+}
+
+// `update-ref <ref> <new> [<old>]` and `update-ref -d <ref> [<old>]`: when `old` is given the
+// reference is only changed if it still holds that value (an empty or null hash for none)
+pub fn update_ref(
+    refname: &str,
+    new: Option<&str>,
+    old: Option<&str>,
+    options: &UpdateRefOptions,
+) -> io::Result<()> {
+    let mut transaction = RefTransaction::new(&options.message);
+    queue_update(
+        &mut transaction,
+        refname,
+        new,
+        old,
+        options.delete,
+        !options.no_deref,
+    )?;
+    transaction.commit()
+}
+
+// `update-ref --stdin`: apply the commands read from standard input in one transaction
+//   update <ref> <new> [<old>], create <ref> <new>, delete <ref> [<old>], verify <ref> [<old>]
+pub fn update_refs_from_stdin(options: &UpdateRefOptions) -> io::Result<()> {
+    let deref = !options.no_deref;
+    let mut transaction = RefTransaction::new(&options.message);
+
+    for (number, line) in io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["update", refname, new] => {
+                queue_update(&mut transaction, refname, Some(new), None, false, deref)?
+            }
+            ["update", refname, new, old] => queue_update(
+                &mut transaction,
+                refname,
+                Some(new),
+                Some(old),
+                false,
+                deref,
+            )?,
+            ["create", refname, new] => {
+                queue_update(&mut transaction, refname, Some(new), Some(""), false, deref)?
+            }
+            ["delete", refname] => {
+                queue_update(&mut transaction, refname, None, None, true, deref)?
+            }
+            ["delete", refname, old] => {
+                queue_update(&mut transaction, refname, None, Some(old), true, deref)?
+            }
+            // A missing old value means the reference must not exist
+            ["verify", refname, old @ ..] if old.len() <= 1 => {
+                let old = object_value(old.first().copied().unwrap_or(""))?;
+                transaction.verify(refname, Some(&old));
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid command on line {}: {}", number + 1, line),
+                ))
+            }
+        }
+    }
+    transaction.commit()
+}
+
+fn queue_update(
+    transaction: &mut RefTransaction,
+    refname: &str,
+    new: Option<&str>,
+    old: Option<&str>,
+    delete: bool,
+    deref: bool,
+) -> io::Result<()> {
+    if refname != "HEAD" {
+        check_ref_format(refname)?;
+    }
+    let old = old.map(object_value).transpose()?;
+    match new.map(object_value).transpose()? {
+        Some(new) if !delete && new != NULL_HASH => {
+            transaction.update(refname, &new, old.as_deref(), deref)
+        }
+        None if !delete => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Missing the new value",
+            ))
+        }
+        // The null hash as new value deletes the reference
+        _ => transaction.delete(refname, old.as_deref(), deref),
+    }
+    Ok(())
+}
+
+// Object named by a value of update-ref, the null hash for an empty value
+fn object_value(value: &str) -> io::Result<String> {
+    if value.is_empty() || value == NULL_HASH {
+        Ok(NULL_HASH.to_string())
+    } else {
+        resolve_revision(value)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolicRefOptions {
+    // Print the short name (e.g. "master" instead of "refs/heads/master")
+    pub short: bool,
+    // Only follow one level of symbolic references
+    pub no_recurse: bool,
+    // Delete the symbolic reference
+    pub delete: bool,
+    // Reason recorded in the reflog when the reference is changed
+    pub message: Option<String>,
+}
+
+// `symbolic-ref`: read, create or delete a symbolic reference
+pub fn symbolic_ref(
+    name: &str,
+    target: Option<&str>,
+    options: &SymbolicRefOptions,
+) -> io::Result<()> {
+    let not_symbolic = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("ref {} is not a symbolic ref", name),
+        )
+    };
+
+    // The name becomes a path under .rgit/, it must not lead outside of it
+    if name != "HEAD" {
+        check_ref_format(name)?;
+    }
+
+    if options.delete {
+        let Some(RefValue::Symbolic(_)) = read_raw_ref(name)? else {
+            return Err(not_symbolic());
+        };
+        return delete_ref(name, None, false);
+    }
+
+    if let Some(target) = target {
+        if !target.starts_with("refs/") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Refusing to point {} outside of refs/", name),
+            ));
+        }
+        check_ref_format(target)?;
+        return write_symbolic_ref(name, target, options.message.as_deref());
+    }
+
+    let Some(RefValue::Symbolic(mut target)) = read_raw_ref(name)? else {
+        return Err(not_symbolic());
+    };
+    if !options.no_recurse {
+        target = resolve_symbolic(&target)?;
+    }
+    if options.short {
+        println!("{}", shorten_ref_name(&target));
+    } else {
+        println!("{}", target);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShowRefOptions {
+    // Only branches (refs/heads/) and/or tags (refs/tags/)
+    pub heads: bool,
+    pub tags: bool,
+    // Also show HEAD
+    pub head: bool,
+    // Patterns are full reference names that must all exist
+    pub verify: bool,
+    // Also show the object annotated tags point to, as "<refname>^{}"
+    pub dereference: bool,
+    // Only print the hashes
+    pub hash: bool,
+    // Print nothing, only report through the exit status
+    pub quiet: bool,
+}
+
+// `show-ref`: list references with the objects they point to.
+// A pattern matches a reference named by it or ending in "/<pattern>".
+pub fn show_ref(patterns: &[String], options: &ShowRefOptions) -> io::Result<()> {
+    let mut refs = vec![];
+    if options.verify {
+        for pattern in patterns {
+            let valid = pattern == "HEAD" || pattern.starts_with("refs/");
+            match read_ref(pattern)? {
+                Some(hash) if valid => refs.push((pattern.clone(), hash)),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("'{}' - not a valid ref", pattern),
+                    ))
+                }
+            }
+        }
+    } else {
+        if options.head {
+            refs.extend(read_ref("HEAD")?.map(|hash| ("HEAD".to_string(), hash)));
+        }
+        for (refname, hash) in list_refs("refs/")? {
+            let kind_selected = (!options.heads && !options.tags)
+                || (options.heads && refname.starts_with("refs/heads/"))
+                || (options.tags && refname.starts_with("refs/tags/"));
+            let pattern_selected = patterns.is_empty()
+                || patterns.iter().any(|pattern| {
+                    refname == *pattern || refname.ends_with(&format!("/{}", pattern))
+                });
+            if kind_selected && pattern_selected {
+                refs.push((refname, hash));
+            }
+        }
+        if refs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No references match",
+            ));
+        }
+    }
+
+    if options.quiet {
+        return Ok(());
+    }
+    for (refname, hash) in refs {
+        print_ref(&refname, &hash, options.hash);
+        if options.dereference {
+            if let Ok((kind, contents)) = read_object(&hash) {
+                if kind == "tag" {
+                    let tag = Tag::parse(&String::from_utf8_lossy(&contents))?;
+                    print_ref(&format!("{}^{{}}", refname), &tag.object, options.hash);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_ref(refname: &str, hash: &str, hash_only: bool) {
+    if hash_only {
+        println!("{}", hash);
+    } else {
+        println!("{} {}", hash, refname);
+    }
+}
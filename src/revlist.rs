@@ -0,0 +1,99 @@
+// `rev-list`: list the commits selected by revisions and ranges, newest first
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+};
+
+use crate::{
+    object::{read_tree, Commit},
+    revwalk::{ancestors, parse_revision_args, walk_commits, WalkOptions},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct RevListOptions {
+    // Revisions, ranges, "--not", "--all", "--branches" and "--tags" in command line order
+    pub revisions: Vec<String>,
+    // Only print the number of commits
+    pub count: bool,
+    // Mark commits with '<' or '>' for the side of "<a>...<b>" they are reachable from
+    pub left_right: bool,
+    // Also list the trees and blobs used by the listed commits
+    pub objects: bool,
+    // Print the parents after each commit
+    pub parents: bool,
+    // Order, limits and filters of the walk
+    pub walk: WalkOptions,
+}
+
+pub fn rev_list(options: &RevListOptions) -> io::Result<()> {
+    let range = parse_revision_args(&options.revisions)?;
+    if range.starts.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No revisions given",
+        ));
+    }
+
+    let walk = WalkOptions {
+        exclude: range.exclude.clone(),
+        ..options.walk.clone()
+    };
+    let commits = walk_commits(&range.starts, &walk)?;
+    let left = ancestors(&range.left)?;
+    let mut output = String::new();
+
+    if options.count {
+        if options.left_right {
+            let left_count = commits
+                .iter()
+                .filter(|(hash, _)| left.contains(hash))
+                .count();
+            output.push_str(&format!("{}\t{}\n", left_count, commits.len() - left_count));
+        } else {
+            output.push_str(&format!("{}\n", commits.len()));
+        }
+        return io::stdout().write_all(output.as_bytes());
+    }
+
+    for (hash, commit) in &commits {
+        if options.left_right {
+            output.push(if left.contains(hash) { '<' } else { '>' });
+        }
+        output.push_str(hash);
+        if options.parents {
+            let shown = if walk.first_parent {
+                1
+            } else {
+                commit.parents.len()
+            };
+            for parent in commit.parents.iter().take(shown) {
+                output.push_str(&format!(" {}", parent));
+            }
+        }
+        output.push('\n');
+    }
+
+    if options.objects {
+        // Trees and blobs of the excluded commits and of their history are already known
+        // to the reader
+        let mut seen: HashSet<String> = HashSet::new();
+        for excluded in ancestors(&range.exclude)? {
+            let tree = Commit::read(&excluded)?.tree;
+            seen.extend(read_tree(&tree)?.into_values());
+            seen.insert(tree);
+        }
+        for (_, commit) in &commits {
+            if seen.insert(commit.tree.clone()) {
+                output.push_str(&format!("{} \n", commit.tree));
+            }
+            for (path, blob) in read_tree(&commit.tree)? {
+                if seen.insert(blob.clone()) {
+                    output.push_str(&format!("{} {}\n", blob, path));
+                }
+            }
+        }
+    }
+
+    io::stdout().write_all(output.as_bytes())
+}
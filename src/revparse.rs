@@ -0,0 +1,689 @@
+// Revision expressions, as described in gitrevisions:
+//   <hash>, <abbreviated hash>   full hash or a unique prefix of at least 4 hex digits
+//   <refname>, @                 HEAD, refs/..., tags, branches and remote branches
+//   @{-<n>}                      n-th branch or commit checked out before the current one
+//   <ref>@{<n>}, @{<n>}          value of a reference (the current branch) n updates ago
+//   <ref>@{<date>}, @{<date>}    value of a reference at a date, e.g. "master@{yesterday}"
+//   <rev>^[<n>], <rev>~[<n>]     n-th parent, n-th generation ancestor following first parents
+//   <rev>^{<type>}, <rev>^{}     peel tags (and commits to their tree) to an object type
+//   <rev>^{/<regex>}, :/<regex>  youngest commit reachable from rev (or any ref) whose message matches
+//   <rev>:<path>, :<path>        blob at a path in the tree of rev, or in the index
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::{
+    head::Head,
+    helper::get_latest_staged_hash,
+    object::{peel_to_commit, read_object, read_tree, Commit, Tag},
+    reflog::{read_reflog, resolve_reflog},
+    refs::{list_refs, read_ref},
+    revwalk::{merge_bases, walk_commits, WalkOptions},
+};
+
+// Shortest abbreviation accepted for object hashes
+const MIN_ABBREV: usize = 4;
+
+fn unknown_revision(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Unknown revision '{}'", name),
+    )
+}
+
+// Full name of the reference a short name stands for, tried in Git's order:
+// the name itself (HEAD-like files and "refs/..."), refs/<name>, refs/tags/<name>,
+// refs/heads/<name>, refs/remotes/<name> and refs/remotes/<name>/HEAD
+pub fn expand_ref_name(name: &str) -> Option<String> {
+    if name == "@" {
+        return Some("HEAD".to_string());
+    }
+    if name.is_empty() || name.contains("..") {
+        return None;
+    }
+    let head_like =
+        name.ends_with("HEAD") && name.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+    let mut candidates = vec![];
+    if head_like || name.starts_with("refs/") {
+        candidates.push(name.to_string());
+    }
+    candidates.extend([
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ]);
+    candidates
+        .into_iter()
+        .find(|refname| Path::new(&format!(".rgit/{}", refname)).is_file())
+}
+
+// Check a reference name against Git's check-ref-format rules: no component starting with '.'
+// or ending with ".lock", no "..", "@{", "//", control characters, spaces or any of ~^:?*[\,
+// and no leading or trailing '/' or trailing '.'
+pub fn check_ref_format(refname: &str) -> io::Result<()> {
+    let invalid = |reason: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid reference name: {}", refname, reason),
+        ))
+    };
+
+    if refname.is_empty() || refname == "@" {
+        return invalid("reserved name");
+    }
+    if refname.starts_with('/') || refname.ends_with('/') || refname.contains("//") {
+        return invalid("empty path component");
+    }
+    if refname.ends_with('.') {
+        return invalid("ends with '.'");
+    }
+    if refname.contains("..") || refname.contains("@{") {
+        return invalid("contains '..' or '@{'");
+    }
+    if let Some(c) = refname
+        .chars()
+        .find(|&c| c.is_ascii_control() || " ~^:?*[\\".contains(c))
+    {
+        return invalid(&format!("contains {:?}", c));
+    }
+    for component in refname.split('/') {
+        if component.starts_with('.') || component.ends_with(".lock") {
+            return invalid("a component starts with '.' or ends with '.lock'");
+        }
+    }
+    Ok(())
+}
+
+// Short name of a reference: "main" for refs/heads/main, "origin/main" for refs/remotes/origin/main
+pub fn shorten_ref_name(refname: &str) -> &str {
+    ["refs/heads/", "refs/tags/", "refs/remotes/", "refs/"]
+        .iter()
+        .find_map(|prefix| refname.strip_prefix(prefix))
+        .unwrap_or(refname)
+}
+
+// Every object whose hash starts with the given prefix
+fn objects_with_prefix(prefix: &str) -> io::Result<Vec<String>> {
+    let dir = format!(".rgit/objects/{}", &prefix[..2]);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(vec![]);
+    };
+    let mut found = vec![];
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix[2..]) {
+            found.push(format!("{}{}", &prefix[..2], name));
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+// Full hash of an abbreviated one, an error lists the candidates when the prefix is ambiguous
+fn expand_abbrev(prefix: &str) -> io::Result<Option<String>> {
+    if prefix.len() < MIN_ABBREV || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(None);
+    }
+    let prefix = prefix.to_ascii_lowercase();
+    let candidates = objects_with_prefix(&prefix)?;
+    if candidates.len() <= 1 {
+        return Ok(candidates.into_iter().next());
+    }
+
+    let mut message = format!("Short object ID {} is ambiguous\nCandidates:", prefix);
+    for candidate in &candidates {
+        let kind = read_object(candidate).map(|(kind, _)| kind)?;
+        message.push_str(&format!("\n  {} {}", unique_abbrev(candidate, 7)?, kind));
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+// Shortest prefix of at least `min_len` digits that names only this object
+pub fn unique_abbrev(hash: &str, min_len: usize) -> io::Result<String> {
+    let mut len = min_len.clamp(MIN_ABBREV, hash.len());
+    while len < hash.len() && objects_with_prefix(&hash[..len])?.len() > 1 {
+        len += 1;
+    }
+    Ok(hash[..len].to_string())
+}
+
+// Branch (or commit when HEAD was detached) checked out before the n-th last checkout,
+// read from the "checkout: moving from <old> to <new>" entries of the HEAD log
+pub fn previous_checkout(n: usize) -> io::Result<String> {
+    read_reflog("HEAD")?
+        .iter()
+        .rev()
+        .filter_map(|entry| entry.message.strip_prefix("checkout: moving from "))
+        .filter_map(|moved| moved.split_once(" to ").map(|(from, _)| from.to_string()))
+        .nth(n.saturating_sub(1))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Only {} checkouts found in the HEAD log",
+                    n.saturating_sub(1)
+                ),
+            )
+        })
+}
+
+// Resolve a name without suffixes: a reference, "@{-<n>}" or a (possibly abbreviated) hash
+fn resolve_name(name: &str) -> io::Result<String> {
+    if name == "HEAD" || name == "@" {
+        return Head::read()?.commit()?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "HEAD does not point to a commit yet",
+            )
+        });
+    }
+
+    if let Some(n) = name.strip_prefix("@{-").and_then(|n| n.strip_suffix('}')) {
+        let n: usize = n.parse().map_err(|_| unknown_revision(name))?;
+        let previous = previous_checkout(n)?;
+        return match read_ref(&format!("refs/heads/{}", previous))? {
+            Some(hash) => Ok(hash),
+            None => resolve_name(&previous),
+        };
+    }
+
+    // "<ref>@{<n>}" and "<ref>@{<date>}" from the reflog, "@{...}" for the current branch
+    if let Some((refname, selector)) = name
+        .strip_suffix('}')
+        .and_then(|name| name.rsplit_once("@{"))
+    {
+        return resolve_reflog(refname, selector);
+    }
+
+    let name_is_hash = name.len() == 40 && name.chars().all(|c| c.is_ascii_hexdigit());
+    if name_is_hash && Path::new(&format!(".rgit/objects/{}/{}", &name[..2], &name[2..])).exists() {
+        return Ok(name.to_string());
+    }
+    if let Some(refname) = expand_ref_name(name) {
+        if let Some(hash) = read_ref(&refname)? {
+            return Ok(hash);
+        }
+    }
+    expand_abbrev(name)?.ok_or_else(|| unknown_revision(name))
+}
+
+// Follow tags (and commits to their tree) until an object of the given type
+fn peel_to_type(hash: &str, kind: &str) -> io::Result<String> {
+    let mut current = hash.to_string();
+    loop {
+        let (current_kind, contents) = read_object(&current)?;
+        if current_kind == kind || kind == "object" || (kind.is_empty() && current_kind != "tag") {
+            return Ok(current);
+        }
+        current = match current_kind.as_str() {
+            "tag" => Tag::parse(&String::from_utf8_lossy(&contents))?.object,
+            "commit" if kind == "tree" => Commit::parse(&String::from_utf8_lossy(&contents))?.tree,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Object '{}' cannot be peeled to a {}", hash, kind),
+                ))
+            }
+        };
+    }
+}
+
+// Youngest commit reachable from the starting commits whose message matches the pattern
+fn find_by_message(starts: &[String], pattern: &str) -> io::Result<String> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    walk_commits(starts, &WalkOptions::default())?
+        .into_iter()
+        .find(|(_, commit)| regex.is_match(&commit.message))
+        .map(|(hash, _)| hash)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No commit message matches '{}'", pattern),
+            )
+        })
+}
+
+// Regular expression of a shell glob ("*" and "?" match any characters, "[...]" a character class)
+pub fn glob_regex(pattern: &str) -> io::Result<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '!' if regex.ends_with('[') => regex.push('^'),
+                        '\\' | '^' => regex.push_str(&format!("\\{}", c)),
+                        _ => regex.push(c),
+                    }
+                }
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+// Commits of HEAD and every reference, the starting points of ":/<regex>"
+fn ref_commits() -> io::Result<Vec<String>> {
+    let mut hashes: Vec<String> = Head::read()?.commit()?.into_iter().collect();
+    for (_, hash) in list_refs("refs/")? {
+        if let Ok(commit) = peel_to_commit(&hash) {
+            hashes.push(commit);
+        }
+    }
+    hashes.sort();
+    hashes.dedup();
+    Ok(hashes)
+}
+
+// Split "<rev>:<path>" at the first ':' outside of "^{...}" and "@{...}"
+pub fn split_revision_path(expr: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in expr.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ':' if depth == 0 => return Some((&expr[..i], &expr[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Resolve a revision expression to the hash of the object it names
+pub fn resolve_revision(expr: &str) -> io::Result<String> {
+    if let Some(pattern) = expr.strip_prefix(":/") {
+        return find_by_message(&ref_commits()?, pattern);
+    }
+
+    if let Some((revision, path)) = split_revision_path(expr) {
+        let path = path.trim_matches('/');
+        if revision.is_empty() {
+            return get_latest_staged_hash(path)?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Path '{}' is not in the index", path),
+                )
+            });
+        }
+        let tree = peel_to_type(&resolve_revision(revision)?, "tree")?;
+        if path.is_empty() {
+            return Ok(tree);
+        }
+        return read_tree(&tree)?.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Path '{}' does not exist in '{}'", path, revision),
+            )
+        });
+    }
+
+    // The name runs until the first suffix, "@{...}" may not contain '^' or '~'
+    let name_end = expr.find(['^', '~']).unwrap_or(expr.len());
+    let mut hash = resolve_name(&expr[..name_end])?;
+
+    let mut rest = &expr[name_end..];
+    while !rest.is_empty() {
+        let count_end = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+        if let Some(after) = rest.strip_prefix("^{") {
+            let end = after.find('}').ok_or_else(|| unknown_revision(expr))?;
+            let inner = &after[..end];
+            hash = match inner.strip_prefix('/') {
+                Some(pattern) => find_by_message(&[peel_to_commit(&hash)?], pattern)?,
+                None if matches!(inner, "" | "object" | "commit" | "tree" | "blob" | "tag") => {
+                    peel_to_type(&hash, inner)?
+                }
+                None => return Err(unknown_revision(expr)),
+            };
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('^') {
+            let digits = count_end(after);
+            let n: usize = if digits == 0 {
+                1
+            } else {
+                after[..digits]
+                    .parse()
+                    .map_err(|_| unknown_revision(expr))?
+            };
+            let commit = peel_to_commit(&hash)?;
+            hash = if n == 0 {
+                commit
+            } else {
+                Commit::read(&commit)?
+                    .parents
+                    .get(n - 1)
+                    .cloned()
+                    .ok_or_else(|| unknown_revision(expr))?
+            };
+            rest = &after[digits..];
+        } else if let Some(after) = rest.strip_prefix('~') {
+            let digits = count_end(after);
+            let n: usize = if digits == 0 {
+                1
+            } else {
+                after[..digits]
+                    .parse()
+                    .map_err(|_| unknown_revision(expr))?
+            };
+            hash = peel_to_commit(&hash)?;
+            for _ in 0..n {
+                hash = Commit::read(&hash)?
+                    .parents
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| unknown_revision(expr))?;
+            }
+            rest = &after[digits..];
+        } else {
+            return Err(unknown_revision(expr));
+        }
+    }
+
+    Ok(hash)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RevParseOptions {
+    // Print the short name of references instead of hashes
+    pub abbrev_ref: bool,
+    // Print unique abbreviated hashes of at least this many digits
+    pub short: Option<usize>,
+    // Require exactly one argument naming an existing object
+    pub verify: bool,
+    // Print the top-level directory of the working tree
+    pub show_toplevel: bool,
+}
+
+// Name of a reference argument for --abbrev-ref: the current branch for HEAD ("HEAD" when detached)
+fn abbrev_ref(arg: &str) -> io::Result<String> {
+    if arg == "HEAD" || arg == "@" {
+        return Ok(match Head::read()?.refname() {
+            Some(refname) => shorten_ref_name(refname).to_string(),
+            None => "HEAD".to_string(),
+        });
+    }
+    if let Some(n) = arg.strip_prefix("@{-").and_then(|n| n.strip_suffix('}')) {
+        return previous_checkout(n.parse().map_err(|_| unknown_revision(arg))?);
+    }
+    match expand_ref_name(arg) {
+        Some(refname) => Ok(shorten_ref_name(&refname).to_string()),
+        None => resolve_revision(arg),
+    }
+}
+
+// Root of the working tree: the closest directory from the current one up that holds .rgit
+fn find_toplevel() -> io::Result<PathBuf> {
+    let current = std::env::current_dir()?;
+    current
+        .ancestors()
+        .find(|dir| dir.join(".rgit").is_dir())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Not a rgit repository (or any of the parent directories): .rgit",
+            )
+        })
+}
+
+pub fn rev_parse(args: &[String], options: &RevParseOptions) -> io::Result<()> {
+    let output = rev_parse_output(args, options)?;
+    io::stdout().write_all(output.as_bytes())
+}
+
+// Lines printed by `rev-parse`
+fn rev_parse_output(args: &[String], options: &RevParseOptions) -> io::Result<String> {
+    let mut output = String::new();
+
+    if options.show_toplevel {
+        output.push_str(&format!("{}\n", find_toplevel()?.display()));
+    }
+
+    if options.verify && args.len() != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Needed a single revision",
+        ));
+    }
+
+    let format = |hash: String| -> io::Result<String> {
+        match options.short {
+            Some(len) => unique_abbrev(&hash, len),
+            None => Ok(hash),
+        }
+    };
+
+    let side = |name: &str| if name.is_empty() { "HEAD" } else { name }.to_string();
+    for arg in args {
+        // "<a>...<b>" prints <b>, <a> and ^<base> for their merge bases
+        // "<from>..<to>" prints <to> and ^<from>, an empty side is HEAD
+        if let Some((a, b)) = arg.split_once("...").filter(|_| !options.verify) {
+            let a = peel_to_commit(&resolve_revision(&side(a))?)?;
+            let b = peel_to_commit(&resolve_revision(&side(b))?)?;
+            let bases = merge_bases(&a, &b)?;
+            output.push_str(&format!("{}\n", format(b)?));
+            output.push_str(&format!("{}\n", format(a)?));
+            for base in bases {
+                output.push_str(&format!("^{}\n", format(base)?));
+            }
+        } else if let Some((from, to)) = arg.split_once("..").filter(|_| !options.verify) {
+            output.push_str(&format!("{}\n", format(resolve_revision(&side(to))?)?));
+            output.push_str(&format!("^{}\n", format(resolve_revision(&side(from))?)?));
+        } else if let Some(name) = arg.strip_prefix('^').filter(|_| !options.verify) {
+            output.push_str(&format!("^{}\n", format(resolve_revision(name)?)?));
+        } else if options.abbrev_ref {
+            output.push_str(&format!("{}\n", abbrev_ref(arg)?));
+        } else {
+            let hash = match resolve_revision(arg) {
+                Ok(hash) => hash,
+                Err(_) if options.verify => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "Needed a single revision",
+                    ))
+                }
+                Err(e) => return Err(e),
+            };
+            output.push_str(&format!("{}\n", format(hash)?));
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        panic::{self, AssertUnwindSafe},
+        sync::Mutex,
+    };
+
+    use super::*;
+    use crate::{helper::hash_and_store_obj, init::init, refs::write_ref};
+
+    // Tests that need a repository change the working directory, they run one at a time
+    static REPOSITORY: Mutex<()> = Mutex::new(());
+
+    fn in_scratch_repository(name: &str, test: impl FnOnce()) {
+        let _guard = REPOSITORY.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = env::temp_dir().join(format!("rgit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(&dir).unwrap();
+
+        init().unwrap();
+        let result = panic::catch_unwind(AssertUnwindSafe(test));
+
+        env::set_current_dir(cwd).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        if let Err(e) = result {
+            panic::resume_unwind(e);
+        }
+    }
+
+    fn store_commit(parents: &[&str], message: &str) -> String {
+        let tree = hash_and_store_obj("tree", "").unwrap();
+        let mut contents: String = parents
+            .iter()
+            .map(|parent| format!("Parent: {}\n", parent))
+            .collect();
+        contents.push_str(&format!(
+            "Tree: {}\nAuthor: A <a@x> 1700000000 +0000\nCommitter: A <a@x> 1700000000 +0000\nMessage: {}",
+            tree, message
+        ));
+        hash_and_store_obj("commit", &contents).unwrap()
+    }
+
+    // c1 - c2 - c3 (master)
+    //        \     \
+    //         c4 -- merge
+    //      (side)
+    // master was set to c1, c2 and c3 in turn
+    fn history() -> [String; 5] {
+        let c1 = store_commit(&[], "one");
+        let c2 = store_commit(&[&c1], "two");
+        let c3 = store_commit(&[&c2], "three");
+        let c4 = store_commit(&[&c2], "four");
+        let merge = store_commit(&[&c3, &c4], "merge");
+        for commit in [&c1, &c2, &c3] {
+            write_ref("refs/heads/master", commit, None, false, "test").unwrap();
+        }
+        write_ref("refs/heads/side", &c4, None, false, "test").unwrap();
+        [c1, c2, c3, c4, merge]
+    }
+
+    fn resolve(expr: &str) -> String {
+        resolve_revision(expr).unwrap()
+    }
+
+    #[test]
+    fn parent_and_ancestor_suffixes() {
+        in_scratch_repository("suffixes", || {
+            let [c1, c2, c3, c4, merge] = history();
+            assert_eq!(resolve(&format!("{}^", merge)), c3);
+            assert_eq!(resolve(&format!("{}^1", merge)), c3);
+            assert_eq!(resolve(&format!("{}^2", merge)), c4);
+            assert_eq!(resolve(&format!("{}^0", merge)), merge);
+            assert_eq!(resolve(&format!("{}~", merge)), c3);
+            assert_eq!(resolve(&format!("{}~3", merge)), c1);
+            assert_eq!(resolve(&format!("{}^2~1", merge)), c2);
+            assert_eq!(resolve(&format!("{}^2^", merge)), c2);
+            assert_eq!(resolve("master^^"), c1);
+            assert_eq!(resolve("master~2"), c1);
+            assert_eq!(resolve("master~0"), c3);
+            assert_eq!(resolve("side^{commit}"), c4);
+            assert_eq!(resolve("master~2^{/one}"), c1);
+
+            for invalid in [
+                format!("{}^3", merge),
+                "master~3".to_string(),
+                "master^x".to_string(),
+            ] {
+                assert!(resolve_revision(&invalid).is_err(), "{}", invalid);
+            }
+        });
+    }
+
+    #[test]
+    fn reflog_suffixes() {
+        in_scratch_repository("reflog", || {
+            let [c1, c2, c3, ..] = history();
+            assert_eq!(resolve("master@{0}"), c3);
+            assert_eq!(resolve("master@{1}"), c2);
+            assert_eq!(resolve("master@{2}"), c1);
+            assert_eq!(resolve("refs/heads/master@{1}"), c2);
+            // An empty name is the current branch
+            assert_eq!(resolve("@{1}"), c2);
+            assert_eq!(resolve("master@{1}~1"), c1);
+            assert_eq!(resolve("HEAD@{1}"), c2);
+
+            let error = resolve_revision("master@{3}").unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        });
+    }
+
+    #[test]
+    fn ranges() {
+        in_scratch_repository("ranges", || {
+            let [_, c2, c3, c4, _] = history();
+            let output = |arg: &str| {
+                rev_parse_output(&[arg.to_string()], &RevParseOptions::default()).unwrap()
+            };
+            // The merge base of both sides is excluded
+            assert_eq!(
+                output("master...side"),
+                format!("{}\n{}\n^{}\n", c4, c3, c2)
+            );
+            assert_eq!(output("side..master"), format!("{}\n^{}\n", c3, c4));
+            assert_eq!(output("^side"), format!("^{}\n", c4));
+            // An empty side is HEAD
+            assert_eq!(output("side..."), format!("{}\n{}\n^{}\n", c3, c4, c2));
+        });
+    }
+
+    #[test]
+    fn valid_ref_names() {
+        for refname in [
+            "refs/heads/master",
+            "refs/heads/feature/login",
+            "refs/tags/v1.0",
+            "HEAD",
+            "a",
+            "refs/heads/fix-1_2",
+            "refs/heads/a/b.c",
+            "refs/heads/a.b.lockx",
+            "refs/heads/@",
+        ] {
+            assert!(check_ref_format(refname).is_ok(), "{}", refname);
+        }
+    }
+
+    #[test]
+    fn invalid_ref_names() {
+        for refname in [
+            "",
+            "@",
+            "/refs/heads/a",
+            "refs/heads/a/",
+            "refs//heads/a",
+            "refs/heads/a.",
+            "refs/heads/a..b",
+            "refs/heads/a@{1}",
+            "refs/heads/a b",
+            "refs/heads/a~1",
+            "refs/heads/a^",
+            "refs/heads/a:b",
+            "refs/heads/a?",
+            "refs/heads/a*",
+            "refs/heads/a[b",
+            "refs/heads/a\\b",
+            "refs/heads/a\tb",
+            "refs/heads/a\u{7f}",
+            "refs/heads/.hidden",
+            "refs/.heads/a",
+            "refs/heads/a.lock",
+            "refs/heads/a.lock/b",
+        ] {
+            let error = check_ref_format(refname).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{}", refname);
+        }
+    }
+}
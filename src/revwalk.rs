@@ -0,0 +1,564 @@
+// Revision walker: list the commits reachable from a set of starting commits
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+    io,
+};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    diff::{commit_changes, Pickaxe},
+    head::Head,
+    object::{peel_to_commit, read_tree, Commit},
+    refs::list_refs,
+    revparse::{glob_regex, resolve_revision},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    // Only follow the first parent of merge commits
+    pub first_parent: bool,
+    // Never show a parent before all of its children
+    pub topo_order: bool,
+    // Output the commits from oldest to newest
+    pub reverse: bool,
+    // Only show commits that change these paths (files or directories)
+    pub paths: Vec<String>,
+    // Only show commits matching the author, message and date filters
+    pub filter: CommitFilter,
+    // Number of matching commits to skip before output
+    pub skip: usize,
+    // Maximum number of commits to output
+    pub max_count: Option<usize>,
+    // Commits reachable from these are not listed ("^<rev>", "<rev>..")
+    pub exclude: Vec<String>,
+    // Only list commits that are descendants of the excluded commits
+    pub ancestry_path: bool,
+}
+
+// Commit filters of `log --author`, `--committer`, `--grep`, `--since` and `--until`
+#[derive(Debug, Clone, Default)]
+pub struct CommitFilter {
+    pub authors: Vec<Regex>,
+    pub committers: Vec<Regex>,
+    pub grep: Vec<Regex>,
+    // Every --grep pattern has to match instead of any of them
+    pub all_match: bool,
+    // Show commits whose message does not match --grep
+    pub invert_grep: bool,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    // Only show commits whose changes match `-S` or `-G`
+    pub pickaxe: Option<Pickaxe>,
+}
+
+// Compile command line patterns into regular expressions
+pub fn compile_patterns(patterns: &[String], ignore_case: bool) -> io::Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+        })
+        .collect()
+}
+
+impl CommitFilter {
+    pub fn matches(&self, commit: &Commit) -> io::Result<bool> {
+        if self
+            .since
+            .is_some_and(|since| commit.committer.timestamp < since)
+            || self
+                .until
+                .is_some_and(|until| commit.committer.timestamp > until)
+        {
+            return Ok(false);
+        }
+
+        let identity_matches = |patterns: &[Regex], name: &str, email: &str| {
+            let identity = format!("{} <{}>", name, email);
+            patterns.is_empty() || patterns.iter().any(|p| p.is_match(&identity))
+        };
+        if !identity_matches(&self.authors, &commit.author.name, &commit.author.email)
+            || !identity_matches(
+                &self.committers,
+                &commit.committer.name,
+                &commit.committer.email,
+            )
+        {
+            return Ok(false);
+        }
+
+        if !self.grep.is_empty() {
+            let matched = if self.all_match {
+                self.grep.iter().all(|p| p.is_match(&commit.message))
+            } else {
+                self.grep.iter().any(|p| p.is_match(&commit.message))
+            };
+            if matched == self.invert_grep {
+                return Ok(false);
+            }
+        }
+
+        match &self.pickaxe {
+            Some(pickaxe) => {
+                for change in commit_changes(commit)? {
+                    if pickaxe.matches_change(&change)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            None => Ok(true),
+        }
+    }
+}
+
+// Check whether a file path is selected by one of the pathspecs (a file or a directory)
+pub fn path_matches(path: &str, pathspecs: &[String]) -> bool {
+    pathspecs.iter().any(|spec| {
+        let spec = spec.trim_end_matches('/');
+        spec.is_empty()
+            || spec == "."
+            || path == spec
+            || path
+                .strip_prefix(spec)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+// Commit waiting in the priority queue, newest commit first
+// Ties are broken by insertion order so the walk is deterministic
+struct QueuedCommit {
+    timestamp: i64,
+    sequence: usize,
+    hash: String,
+}
+
+impl PartialEq for QueuedCommit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedCommit {}
+
+impl PartialOrd for QueuedCommit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedCommit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+// Commits selected by revision arguments: reachable from `starts` but not from `exclude`
+#[derive(Debug, Clone, Default)]
+pub struct RevisionRange {
+    pub starts: Vec<String>,
+    pub exclude: Vec<String>,
+    // Left sides of "<a>...<b>", to tell the sides apart with --left-right
+    pub left: Vec<String>,
+}
+
+// Commits named by "--all", "--branches[=<glob>]" and "--tags[=<glob>]", None for other arguments
+fn ref_pattern_commits(arg: &str) -> io::Result<Option<Vec<String>>> {
+    let (prefix, pattern) = match arg.split_once('=').unwrap_or((arg, "")) {
+        ("--all", "") => ("refs/", ""),
+        ("--branches", pattern) => ("refs/heads/", pattern),
+        ("--tags", pattern) => ("refs/tags/", pattern),
+        _ => return Ok(None),
+    };
+    let glob = if pattern.is_empty() {
+        None
+    } else if pattern.contains(['*', '?', '[']) {
+        Some(glob_regex(pattern)?)
+    } else {
+        // A pattern without wildcards names a hierarchy, e.g. "feature" for "feature/*"
+        Some(glob_regex(&format!("{}/*", pattern.trim_end_matches('/')))?)
+    };
+
+    let mut commits = vec![];
+    if arg == "--all" {
+        commits.extend(Head::read()?.commit()?);
+    }
+    for (refname, hash) in list_refs(prefix)? {
+        if glob
+            .as_ref()
+            .is_some_and(|glob| !glob.is_match(&refname[prefix.len()..]))
+        {
+            continue;
+        }
+        // References to trees or blobs name no commits
+        if let Ok(commit) = peel_to_commit(&hash) {
+            commits.push(commit);
+        }
+    }
+    Ok(Some(commits))
+}
+
+// Parse revision arguments: "<rev>", "^<rev>", "<a>..<b>", "<a>...<b>" (an empty side is HEAD),
+// "--all", "--branches[=<glob>]", "--tags[=<glob>]", and "--not" which flips the meaning of
+// the arguments after it
+pub fn parse_revision_args(args: &[String]) -> io::Result<RevisionRange> {
+    let resolve = |name: &str| -> io::Result<String> {
+        let name = if name.is_empty() { "HEAD" } else { name };
+        peel_to_commit(&resolve_revision(name)?)
+    };
+
+    let mut range = RevisionRange::default();
+    let mut negated = false;
+    for arg in args {
+        if arg == "--not" {
+            negated = !negated;
+            continue;
+        }
+
+        // (commit, excluded) pairs named by the argument
+        let mut named: Vec<(String, bool)> = vec![];
+        if let Some(commits) = ref_pattern_commits(arg)? {
+            named.extend(commits.into_iter().map(|commit| (commit, false)));
+        } else if let Some((a, b)) = arg.split_once("...") {
+            // Symmetric difference: reachable from either side but not from both
+            let (a, b) = (resolve(a)?, resolve(b)?);
+            named.extend(merge_bases(&a, &b)?.into_iter().map(|base| (base, true)));
+            range.left.push(a.clone());
+            named.push((a, false));
+            named.push((b, false));
+        } else if let Some((from, to)) = arg.split_once("..") {
+            named.push((resolve(from)?, true));
+            named.push((resolve(to)?, false));
+        } else if let Some(name) = arg.strip_prefix('^') {
+            named.push((resolve(name)?, true));
+        } else {
+            named.push((resolve(arg)?, false));
+        }
+
+        for (commit, excluded) in named {
+            if excluded != negated {
+                range.exclude.push(commit);
+            } else {
+                range.starts.push(commit);
+            }
+        }
+    }
+    Ok(range)
+}
+
+// Best common ancestors of two commits: common ancestors that are not ancestors of another one
+pub fn merge_bases(a: &str, b: &str) -> io::Result<Vec<String>> {
+    let from_b = ancestors(&[b.to_string()])?;
+    let common: Vec<String> = ancestors(&[a.to_string()])?
+        .into_iter()
+        .filter(|hash| from_b.contains(hash))
+        .collect();
+
+    let mut parents = vec![];
+    for hash in &common {
+        parents.extend(Commit::read(hash)?.parents);
+    }
+    let below = ancestors(&parents)?;
+    let mut bases: Vec<String> = common
+        .into_iter()
+        .filter(|hash| !below.contains(hash))
+        .collect();
+    bases.sort();
+    Ok(bases)
+}
+
+// Every commit reachable from the given commits, themselves included
+pub fn ancestors(starts: &[String]) -> io::Result<HashSet<String>> {
+    let mut found: HashSet<String> = HashSet::new();
+    let mut stack: Vec<String> = starts.to_vec();
+    while let Some(hash) = stack.pop() {
+        if found.insert(hash.clone()) {
+            stack.extend(Commit::read(&hash)?.parents);
+        }
+    }
+    Ok(found)
+}
+
+// Walk the history from the starting commits, newest first
+// Every commit is listed once, even when it is reachable through several merge parents
+pub fn walk_commits(starts: &[String], options: &WalkOptions) -> io::Result<Vec<(String, Commit)>> {
+    let mut queue = BinaryHeap::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut sequence = 0;
+    let mut walked: Vec<(String, Commit)> = vec![];
+
+    // With path limiting, commits that do not change the paths are hidden and
+    // merges only follow a parent with the same content for the paths
+    let mut hidden: HashSet<String> = HashSet::new();
+    let mut followed_parents: HashMap<String, Vec<String>> = HashMap::new();
+    let mut path_trees: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+
+    // Without a pass over the whole history (to sort it or rewrite its parents) commits are
+    // selected as they are walked, and the walk stops at --max-count
+    let streaming = !options.topo_order && !options.ancestry_path && options.paths.is_empty();
+    let mut skip = options.skip;
+
+    let excluded = ancestors(&options.exclude)?;
+    let mut push = |queue: &mut BinaryHeap<QueuedCommit>,
+                    seen: &mut HashSet<String>,
+                    hash: &str|
+     -> io::Result<Option<Commit>> {
+        if excluded.contains(hash) || !seen.insert(hash.to_string()) {
+            return Ok(None);
+        }
+        let commit = Commit::read(hash)?;
+        queue.push(QueuedCommit {
+            timestamp: commit.committer.timestamp,
+            sequence,
+            hash: hash.to_string(),
+        });
+        sequence += 1;
+        Ok(Some(commit))
+    };
+
+    let mut loaded: HashMap<String, Commit> = HashMap::new();
+    for start in starts {
+        if let Some(commit) = push(&mut queue, &mut seen, start)? {
+            loaded.insert(start.clone(), commit);
+        }
+    }
+
+    while let Some(QueuedCommit { hash, .. }) = queue.pop() {
+        if streaming && options.max_count.is_some_and(|max| walked.len() >= max) {
+            break;
+        }
+        let commit = loaded.remove(&hash).expect("queued commits are loaded");
+
+        let mut parents: Vec<String> = if options.first_parent {
+            commit.parents.iter().take(1).cloned().collect()
+        } else {
+            commit.parents.clone()
+        };
+
+        if !options.paths.is_empty() {
+            let own_tree = path_tree(&mut path_trees, &commit.tree, &options.paths)?;
+            let mut same_parent = None;
+            for parent in &parents {
+                let parent_tree = Commit::read(parent)?.tree;
+                if path_tree(&mut path_trees, &parent_tree, &options.paths)? == own_tree {
+                    same_parent = Some(parent.clone());
+                    break;
+                }
+            }
+
+            match same_parent {
+                // A merge with the same content as one of its parents only follows that parent
+                Some(parent) if parents.len() > 1 => {
+                    parents = vec![parent];
+                    hidden.insert(hash.clone());
+                }
+                Some(_) => {
+                    hidden.insert(hash.clone());
+                }
+                None if parents.is_empty() && own_tree.is_empty() => {
+                    hidden.insert(hash.clone());
+                }
+                None => {}
+            }
+            followed_parents.insert(hash.clone(), parents.clone());
+        }
+
+        for parent in &parents {
+            if let Some(parent_commit) = push(&mut queue, &mut seen, parent)? {
+                loaded.insert(parent.clone(), parent_commit);
+            }
+        }
+
+        if streaming {
+            if !options.filter.matches(&commit)? {
+                continue;
+            }
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+        }
+        walked.push((hash, commit));
+    }
+
+    if streaming {
+        if options.reverse {
+            walked.reverse();
+        }
+        return Ok(walked);
+    }
+
+    if options.ancestry_path && !options.exclude.is_empty() {
+        let mut on_path: HashSet<String> = options.exclude.iter().cloned().collect();
+        // Children are usually walked before their parents, repeat oldest first until stable
+        loop {
+            let found = on_path.len();
+            for (hash, commit) in walked.iter().rev() {
+                if commit.parents.iter().any(|parent| on_path.contains(parent)) {
+                    on_path.insert(hash.clone());
+                }
+            }
+            if on_path.len() == found {
+                break;
+            }
+        }
+        walked.retain(|(hash, _)| on_path.contains(hash));
+    }
+
+    let mut commits: Vec<(String, Commit)> = if options.paths.is_empty() {
+        walked
+    } else {
+        // Rewrite parents to the nearest shown ancestors so the history stays connected
+        let mut rewritten: HashMap<String, Vec<String>> = HashMap::new();
+        walked
+            .into_iter()
+            .filter(|(hash, _)| !hidden.contains(hash))
+            .map(|(hash, mut commit)| {
+                commit.parents = followed_parents[&hash]
+                    .iter()
+                    .flat_map(|p| shown_ancestors(p, &hidden, &followed_parents, &mut rewritten))
+                    .fold(vec![], |mut parents, p| {
+                        if !parents.contains(&p) {
+                            parents.push(p);
+                        }
+                        parents
+                    });
+                (hash, commit)
+            })
+            .collect()
+    };
+
+    if options.topo_order {
+        commits = topo_sort(commits, options.first_parent);
+    }
+
+    select_commits(commits, options)
+}
+
+// Apply the commit filters, --skip, --max-count and --reverse to walked commits
+pub fn select_commits(
+    commits: Vec<(String, Commit)>,
+    options: &WalkOptions,
+) -> io::Result<Vec<(String, Commit)>> {
+    let mut selected = vec![];
+    let mut skip = options.skip;
+    for (hash, commit) in commits {
+        if options.max_count.is_some_and(|max| selected.len() >= max) {
+            break;
+        }
+        if !options.filter.matches(&commit)? {
+            continue;
+        }
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        selected.push((hash, commit));
+    }
+    if options.reverse {
+        selected.reverse();
+    }
+
+    Ok(selected)
+}
+
+// Files of a tree selected by the pathspecs, cached by tree hash
+fn path_tree(
+    cache: &mut HashMap<String, BTreeMap<String, String>>,
+    tree_hash: &str,
+    paths: &[String],
+) -> io::Result<BTreeMap<String, String>> {
+    if let Some(tree) = cache.get(tree_hash) {
+        return Ok(tree.clone());
+    }
+    let tree: BTreeMap<String, String> = read_tree(tree_hash)?
+        .into_iter()
+        .filter(|(path, _)| path_matches(path, paths))
+        .collect();
+    cache.insert(tree_hash.to_string(), tree.clone());
+    Ok(tree)
+}
+
+// Nearest ancestors of a commit (itself included) that are not hidden by path limiting
+fn shown_ancestors(
+    hash: &str,
+    hidden: &HashSet<String>,
+    followed_parents: &HashMap<String, Vec<String>>,
+    memo: &mut HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    if !hidden.contains(hash) {
+        return vec![hash.to_string()];
+    }
+    if let Some(ancestors) = memo.get(hash) {
+        return ancestors.clone();
+    }
+
+    let mut ancestors: Vec<String> = vec![];
+    for parent in followed_parents.get(hash).into_iter().flatten() {
+        for ancestor in shown_ancestors(parent, hidden, followed_parents, memo) {
+            if !ancestors.contains(&ancestor) {
+                ancestors.push(ancestor);
+            }
+        }
+    }
+    memo.insert(hash.to_string(), ancestors.clone());
+    ancestors
+}
+
+// Reorder date ordered commits so that children always come before their parents,
+// keeping each line of history together
+fn topo_sort(commits: Vec<(String, Commit)>, first_parent: bool) -> Vec<(String, Commit)> {
+    let parents_of = |commit: &Commit| -> Vec<String> {
+        if first_parent {
+            commit.parents.iter().take(1).cloned().collect()
+        } else {
+            commit.parents.clone()
+        }
+    };
+
+    // Count how many listed children every commit has
+    let mut child_count: HashMap<String, usize> = HashMap::new();
+    for (_, commit) in &commits {
+        for parent in parents_of(commit) {
+            *child_count.entry(parent).or_insert(0) += 1;
+        }
+    }
+
+    let mut by_hash: HashMap<String, Commit> = HashMap::new();
+    let mut tips: Vec<String> = vec![];
+    for (hash, commit) in commits {
+        if child_count.get(&hash).copied().unwrap_or(0) == 0 {
+            tips.push(hash.clone());
+        }
+        by_hash.insert(hash, commit);
+    }
+
+    // Depth-first from the newest tip, a parent is output once all of its children are
+    let mut stack: Vec<String> = tips.into_iter().rev().collect();
+    let mut sorted = vec![];
+    while let Some(hash) = stack.pop() {
+        let Some(commit) = by_hash.remove(&hash) else {
+            continue;
+        };
+
+        for parent in parents_of(&commit).iter().rev() {
+            if let Some(count) = child_count.get_mut(parent) {
+                *count -= 1;
+                if *count == 0 {
+                    stack.push(parent.clone());
+                }
+            }
+        }
+        sorted.push((hash, commit));
+    }
+
+    sorted
+}
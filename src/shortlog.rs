@@ -0,0 +1,257 @@
+// `shortlog`: summarize the commits by author, committer or trailer value
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, IsTerminal},
+};
+
+use crate::{
+    head::Head,
+    mailmap::Mailmap,
+    object::{Commit, Identity},
+    revwalk::{parse_revision_args, walk_commits, RevisionRange, WalkOptions},
+};
+
+// What the commits are grouped by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShortlogGroup {
+    Author,
+    Committer,
+    // Value of a trailer, e.g. "trailer:co-authored-by"
+    Trailer(String),
+}
+
+impl ShortlogGroup {
+    pub fn parse(value: &str) -> io::Result<ShortlogGroup> {
+        match value {
+            "author" => Ok(ShortlogGroup::Author),
+            "committer" => Ok(ShortlogGroup::Committer),
+            _ => match value.strip_prefix("trailer:") {
+                Some(key) if !key.is_empty() => Ok(ShortlogGroup::Trailer(key.to_string())),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown group type '{}'", value),
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShortlogOptions {
+    // Revisions or ranges to summarize, defaults to HEAD (or a log read from stdin)
+    pub revisions: Vec<String>,
+    // Only show the number of commits of every group
+    pub summary: bool,
+    // Sort by number of commits instead of name
+    pub numbered: bool,
+    // Show the email next to the name
+    pub email: bool,
+    // Group by, defaults to the author
+    pub groups: Vec<ShortlogGroup>,
+}
+
+// Group key: the name, with the email when requested
+fn identity_key(name: &str, email: &str, options: &ShortlogOptions) -> String {
+    if options.email {
+        format!("{} <{}>", name, email)
+    } else {
+        name.to_string()
+    }
+}
+
+// Group keys of a commit, a commit is counted once per distinct key
+fn commit_keys(commit: &Commit, mailmap: &Mailmap, options: &ShortlogOptions) -> Vec<String> {
+    let mut keys: Vec<String> = vec![];
+    let identity = |identity: &Identity| {
+        let (name, email) = mailmap.lookup(&identity.name, &identity.email);
+        identity_key(&name, &email, options)
+    };
+
+    for group in &options.groups {
+        let group_keys = match group {
+            ShortlogGroup::Author => vec![identity(&commit.author)],
+            ShortlogGroup::Committer => vec![identity(&commit.committer)],
+            ShortlogGroup::Trailer(key) => commit
+                .trailers()
+                .into_iter()
+                .filter(|t| t.key.eq_ignore_ascii_case(key))
+                .map(|t| {
+                    // Identities in trailers are mapped like authors
+                    let value = t.unfolded_value();
+                    match Identity::parse(&value) {
+                        Some(id) if value.contains('<') => identity(&id),
+                        _ => value,
+                    }
+                })
+                .collect(),
+        };
+        for key in group_keys {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+// Read the authors and subjects of the default `log` output
+fn read_log_stream(
+    input: impl BufRead,
+    mailmap: &Mailmap,
+    options: &ShortlogOptions,
+) -> io::Result<Vec<(String, String)>> {
+    let mut entries = vec![];
+    let mut author: Option<String> = None;
+    for line in input.lines() {
+        let line = line?;
+        if let Some(value) = line.strip_prefix("Author: ") {
+            author = Identity::parse(value).map(|id| {
+                let (name, email) = mailmap.lookup(&id.name, &id.email);
+                identity_key(&name, &email, options)
+            });
+        } else if let Some(subject) = line.strip_prefix("    ") {
+            // The first message line after the author is the subject
+            if let Some(author) = author.take() {
+                entries.push((author, subject.trim().to_string()));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+pub fn shortlog(options: &ShortlogOptions) -> io::Result<()> {
+    let mailmap = Mailmap::load()?;
+    let mut options = options.clone();
+    if options.groups.is_empty() {
+        options.groups.push(ShortlogGroup::Author);
+    }
+
+    // (group key, subject), newest commit first
+    let entries: Vec<(String, String)> =
+        if options.revisions.is_empty() && !io::stdin().is_terminal() {
+            read_log_stream(io::stdin().lock(), &mailmap, &options)?
+        } else {
+            let range = if options.revisions.is_empty() {
+                RevisionRange {
+                    starts: Head::read()?.commit()?.into_iter().collect(),
+                    ..RevisionRange::default()
+                }
+            } else {
+                parse_revision_args(&options.revisions)?
+            };
+            let walk = WalkOptions {
+                exclude: range.exclude,
+                ..WalkOptions::default()
+            };
+            walk_commits(&range.starts, &walk)?
+                .into_iter()
+                .flat_map(|(_, commit)| {
+                    let subject = commit.subject().to_string();
+                    commit_keys(&commit, &mailmap, &options)
+                        .into_iter()
+                        .map(move |key| (key, subject.clone()))
+                })
+                .collect()
+        };
+
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, subject) in entries {
+        groups.entry(key).or_default().push(subject);
+    }
+    let mut groups: Vec<(String, Vec<String>)> = groups.into_iter().collect();
+    if options.numbered {
+        groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+    } else {
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+
+    for (key, subjects) in groups {
+        if options.summary {
+            println!("{:>6}\t{}", subjects.len(), key);
+            continue;
+        }
+        println!("{} ({}):", key, subjects.len());
+        // Oldest commit first
+        for subject in subjects.iter().rev() {
+            println!("      {}", subject);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str, email: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+            timestamp: 0,
+            timezone: "+0000".to_string(),
+        }
+    }
+
+    fn commit(message: &str) -> Commit {
+        Commit {
+            tree: String::new(),
+            parents: vec![],
+            author: identity("Ann", "ann@example.com"),
+            committer: identity("Bob", "bob@example.com"),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_groups() {
+        assert_eq!(
+            ShortlogGroup::parse("author").unwrap(),
+            ShortlogGroup::Author
+        );
+        assert_eq!(
+            ShortlogGroup::parse("committer").unwrap(),
+            ShortlogGroup::Committer
+        );
+        assert_eq!(
+            ShortlogGroup::parse("trailer:co-authored-by").unwrap(),
+            ShortlogGroup::Trailer("co-authored-by".to_string())
+        );
+        for invalid in ["", "Author", "trailer:", "trailer", "reviewer"] {
+            let error = ShortlogGroup::parse(invalid).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn keys_of_every_group_once() {
+        let commit = commit(
+            "Subject\n\nCo-authored-by: Bob <bob@example.com>\nCo-authored-by: Cy <cy@example.com>\n",
+        );
+        let mailmap = Mailmap::parse("Cyril <cy@example.com>");
+        let options = ShortlogOptions {
+            groups: vec![
+                ShortlogGroup::Author,
+                ShortlogGroup::Committer,
+                ShortlogGroup::Trailer("co-authored-by".to_string()),
+            ],
+            ..ShortlogOptions::default()
+        };
+        assert_eq!(
+            commit_keys(&commit, &mailmap, &options),
+            vec!["Ann", "Bob", "Cyril"]
+        );
+
+        let options = ShortlogOptions {
+            email: true,
+            groups: vec![ShortlogGroup::Trailer("Co-Authored-By".to_string())],
+            ..ShortlogOptions::default()
+        };
+        assert_eq!(
+            commit_keys(&commit, &mailmap, &options),
+            vec!["Bob <bob@example.com>", "Cyril <cy@example.com>"]
+        );
+    }
+}
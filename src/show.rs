@@ -0,0 +1,138 @@
+// `show`: print commits with their patch, annotated tags, trees and blobs
+
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+use crate::{
+    diff::{commit_changes, format_file_patch},
+    mailmap::Mailmap,
+    object::{peel_to_commit, read_object, read_tree, Commit, Tag},
+    pretty::{format_date, pretty_commit, DateMode, FormatContext, PrettyFormat},
+    revparse::{resolve_revision, split_revision_path},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ShowOptions {
+    // Built-in format or format string for commits, defaults to "medium"
+    pub pretty: Option<PrettyFormat>,
+    // How dates are shown
+    pub date_mode: DateMode,
+    // Show abbreviated commit hashes
+    pub abbrev_commit: bool,
+    // Do not show the patch of commits
+    pub no_patch: bool,
+}
+
+// Object named on the command line
+enum Shown {
+    Object(String),
+    // Directory of a tree ("<rev>:<dir>"), trees store every file under its full path
+    Directory(String, BTreeSet<String>),
+}
+
+// Resolve a revision expression, "<rev>:<dir>" lists the files of a directory
+fn resolve_shown(name: &str) -> io::Result<Shown> {
+    let error = match resolve_revision(name) {
+        Ok(hash) => return Ok(Shown::Object(hash)),
+        Err(e) => e,
+    };
+
+    // Directories have no tree object of their own
+    let Some((revision, path)) = split_revision_path(name).filter(|(r, _)| !r.is_empty()) else {
+        return Err(error);
+    };
+    let commit = Commit::read(&peel_to_commit(&resolve_revision(revision)?)?)?;
+    let tree = read_tree(&commit.tree)?;
+    let entries = tree_entries(tree.keys(), &format!("{}/", path.trim_matches('/')));
+    if entries.is_empty() {
+        return Err(error);
+    }
+    Ok(Shown::Directory(name.to_string(), entries))
+}
+
+// Names directly below the prefix, directories with a trailing '/'
+fn tree_entries<'a>(paths: impl Iterator<Item = &'a String>, prefix: &str) -> BTreeSet<String> {
+    paths
+        .filter_map(|path| path.strip_prefix(prefix))
+        .map(|rest| match rest.split_once('/') {
+            Some((dir, _)) => format!("{}/", dir),
+            None => rest.to_string(),
+        })
+        .collect()
+}
+
+pub fn show(names: &[String], options: &ShowOptions) -> io::Result<()> {
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match resolve_shown(name)? {
+            Shown::Object(hash) => show_object(name, &hash, options)?,
+            Shown::Directory(name, entries) => {
+                println!("tree {}\n", name);
+                for entry in entries {
+                    println!("{}", entry);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn show_object(name: &str, hash: &str, options: &ShowOptions) -> io::Result<()> {
+    let (kind, contents) = read_object(hash)?;
+    match kind.as_str() {
+        "commit" => {
+            let commit = Commit::parse(&String::from_utf8_lossy(&contents))?;
+            show_commit(hash, &commit, options)
+        }
+        "tag" => {
+            let tag = Tag::parse(&String::from_utf8_lossy(&contents))?;
+            println!("tag {}", tag.name);
+            if let Some(tagger) = &tag.tagger {
+                println!("Tagger: {} <{}>", tagger.name, tagger.email);
+                println!("Date:   {}", format_date(tagger, options.date_mode));
+            }
+            println!("\n{}\n", tag.message.trim_end());
+            show_object(&tag.object, &tag.object, options)
+        }
+        "tree" => {
+            let tree = read_tree(hash)?;
+            println!("tree {}\n", name);
+            for entry in tree_entries(tree.keys(), "") {
+                println!("{}", entry);
+            }
+            Ok(())
+        }
+        _ => io::stdout().write_all(&contents),
+    }
+}
+
+fn show_commit(hash: &str, commit: &Commit, options: &ShowOptions) -> io::Result<()> {
+    let pretty = options.pretty.clone().unwrap_or(PrettyFormat::Medium);
+    let context = FormatContext {
+        date_mode: options.date_mode,
+        abbrev_commit: options.abbrev_commit,
+        mailmap: Mailmap::load()?,
+        ..FormatContext::default()
+    };
+    let mut output = pretty_commit(&pretty, hash, commit, &context)?;
+
+    if !options.no_patch {
+        let mut patch = String::new();
+        for change in commit_changes(commit)? {
+            patch.push_str(&format_file_patch(&change)?);
+        }
+        if !patch.is_empty() {
+            let multi_line = !matches!(pretty, PrettyFormat::Oneline | PrettyFormat::Format(_));
+            let separator = if multi_line { "\n\n" } else { "\n" };
+            output = format!("{}{}{}", output, separator, patch.trim_end());
+        }
+    }
+
+    println!("{}", output);
+    Ok(())
+}
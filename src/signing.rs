@@ -0,0 +1,263 @@
+// SSH signatures (ed25519 keys) for commits and tags, verified against an allowed signers file
+
+use std::{fs, io, path::Path};
+
+use ssh_key::{HashAlg, LineEnding, PrivateKey, PublicKey, SshSig};
+
+use crate::config::get_config;
+
+// Signature namespace used by Git for commit and tag signatures
+const SIGNATURE_NAMESPACE: &str = "git";
+
+// Header holding the armored signature inside commit and tag objects
+const SIGNATURE_HEADER: &str = "Gpgsig: ";
+
+fn signing_error(e: ssh_key::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+// Sign the payload with the key file given on the command line or configured with `user.signingKey`
+// A path to the public key ("id_ed25519.pub") is accepted and mapped to its private key
+pub fn sign_payload(payload: &str, signing_key: Option<&str>) -> io::Result<String> {
+    let key_path = match signing_key.filter(|k| !k.is_empty()) {
+        Some(key) => key.to_string(),
+        None => get_config("[user]", "signingKey")?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No signing key configured, set user.signingKey to an ed25519 private key file",
+            )
+        })?,
+    };
+    let key_path = key_path.strip_suffix(".pub").unwrap_or(&key_path);
+
+    let private_key = PrivateKey::read_openssh_file(Path::new(key_path)).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot read signing key '{}': {}", key_path, e),
+        )
+    })?;
+    if private_key.is_encrypted() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Signing key '{}' is encrypted, which is not supported",
+                key_path
+            ),
+        ));
+    }
+
+    let signature = private_key
+        .sign(SIGNATURE_NAMESPACE, HashAlg::Sha512, payload.as_bytes())
+        .map_err(signing_error)?;
+    signature.to_pem(LineEnding::LF).map_err(signing_error)
+}
+
+// Insert the signature header right before the "Message: " line of an object
+pub fn embed_signature(payload: &str, signature: &str) -> String {
+    let header = format!(
+        "{}{}\n",
+        SIGNATURE_HEADER,
+        signature.trim_end().replace('\n', "\n ")
+    );
+    match payload.find("Message: ") {
+        Some(pos) => format!("{}{}{}", &payload[..pos], header, &payload[pos..]),
+        None => format!("{}\n{}", payload, header),
+    }
+}
+
+// Split a signed object into the signed payload and its signature
+pub fn split_signature(contents: &str) -> (String, Option<String>) {
+    // Only look at the headers, never inside the message
+    let headers_end = contents.find("Message: ").unwrap_or(contents.len());
+    let start = if contents.starts_with(SIGNATURE_HEADER) {
+        Some(0)
+    } else {
+        contents[..headers_end]
+            .find(&format!("\n{}", SIGNATURE_HEADER))
+            .map(|pos| pos + 1)
+    };
+    let Some(start) = start else {
+        return (contents.to_string(), None);
+    };
+
+    // The signature continues on every following line that starts with a space
+    let mut end = start;
+    let mut signature_lines = vec![];
+    for (i, line) in contents[start..].split_inclusive('\n').enumerate() {
+        if i > 0 && !line.starts_with(' ') {
+            break;
+        }
+        let line = line.trim_end_matches('\n');
+        signature_lines.push(if i == 0 {
+            &line[SIGNATURE_HEADER.len()..]
+        } else {
+            &line[1..]
+        });
+        end += line.len() + 1;
+    }
+    let end = end.min(contents.len());
+
+    let payload = format!("{}{}", &contents[..start], &contents[end..]);
+    (payload, Some(signature_lines.join("\n") + "\n"))
+}
+
+// Outcome of checking a signature
+pub struct SignatureCheck {
+    pub good: bool,
+    pub principal: Option<String>,
+    pub key_type: String,
+    pub fingerprint: String,
+}
+
+impl SignatureCheck {
+    // Human readable result, similar to `ssh-keygen -Y verify`
+    pub fn describe(&self) -> String {
+        match (&self.principal, self.good) {
+            (Some(principal), true) => format!(
+                "Good \"{}\" signature for {} with {} key {}",
+                SIGNATURE_NAMESPACE, principal, self.key_type, self.fingerprint
+            ),
+            (None, true) => format!(
+                "Good \"{}\" signature with {} key {}\nNo principal matched.",
+                SIGNATURE_NAMESPACE, self.key_type, self.fingerprint
+            ),
+            _ => format!(
+                "Bad \"{}\" signature with {} key {}",
+                SIGNATURE_NAMESPACE, self.key_type, self.fingerprint
+            ),
+        }
+    }
+
+    // A signature is trusted when it is valid and its key is in the allowed signers file
+    pub fn is_trusted(&self) -> bool {
+        self.good && self.principal.is_some()
+    }
+}
+
+// Verify the signature over the payload and look the key up in `gpg.ssh.allowedSignersFile`
+pub fn verify_signature(payload: &str, signature: &str) -> io::Result<SignatureCheck> {
+    let allowed_signers_path = get_config("[gpg \"ssh\"]", "allowedSignersFile")?
+        .filter(|p| Path::new(p).exists())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "gpg.ssh.allowedSignersFile needs to be configured and exist for ssh signature verification",
+            )
+        })?;
+
+    let signature = SshSig::from_pem(signature).map_err(signing_error)?;
+    let public_key = PublicKey::from(signature.public_key().clone());
+
+    let good = signature.namespace() == SIGNATURE_NAMESPACE
+        && public_key
+            .verify(SIGNATURE_NAMESPACE, payload.as_bytes(), &signature)
+            .is_ok();
+
+    let allowed_signers = fs::read_to_string(allowed_signers_path)?;
+    let principal = find_principal(&allowed_signers, &public_key);
+
+    Ok(SignatureCheck {
+        good,
+        principal,
+        key_type: public_key
+            .algorithm()
+            .as_str()
+            .trim_start_matches("ssh-")
+            .to_ascii_uppercase(),
+        fingerprint: public_key.fingerprint(HashAlg::Sha256).to_string(),
+    })
+}
+
+// Verify a commit or tag object, returns None when the object is not signed
+pub fn verify_object(contents: &str) -> io::Result<Option<SignatureCheck>> {
+    match split_signature(contents) {
+        (payload, Some(signature)) => Ok(Some(verify_signature(&payload, &signature)?)),
+        (_, None) => Ok(None),
+    }
+}
+
+// Find the principals allowed to sign with the key, following the `ssh-keygen` allowed signers format:
+// "<principal>[,<principal>...] [options] <key type> <base64 key> [comment]"
+fn find_principal(allowed_signers: &str, public_key: &PublicKey) -> Option<String> {
+    for line in allowed_signers.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_allowed_signer_fields(line);
+        if fields.len() < 3 {
+            continue;
+        }
+
+        // Options are present when the second field is not a key type
+        let (options, key_fields) = if is_key_type(&fields[1]) {
+            ("", &fields[1..])
+        } else {
+            (fields[1].as_str(), &fields[2..])
+        };
+        if key_fields.len() < 2 {
+            continue;
+        }
+
+        if !namespace_allowed(options) {
+            continue;
+        }
+
+        let Ok(allowed_key) =
+            PublicKey::from_openssh(&format!("{} {}", key_fields[0], key_fields[1]))
+        else {
+            continue;
+        };
+        if allowed_key.key_data() == public_key.key_data() {
+            return Some(fields[0].clone());
+        }
+    }
+
+    None
+}
+
+// Split a line on whitespace while keeping double-quoted option values together
+fn split_allowed_signer_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    fields.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        fields.push(current);
+    }
+
+    fields
+}
+
+fn is_key_type(field: &str) -> bool {
+    field.starts_with("ssh-") || field.starts_with("ecdsa-") || field.starts_with("sk-")
+}
+
+// Check the `namespaces="..."` option of an allowed signers entry
+fn namespace_allowed(options: &str) -> bool {
+    let Some(pos) = options.find("namespaces=\"") else {
+        return true;
+    };
+    let namespaces = &options[pos + "namespaces=\"".len()..];
+    namespaces
+        .split('"')
+        .next()
+        .unwrap_or("")
+        .split(',')
+        .any(|n| n == SIGNATURE_NAMESPACE || n == "*")
+}
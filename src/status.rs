@@ -3,9 +3,15 @@ use std::{
     io::{self, BufRead, BufReader},
 };
 
-use crate::helper::{compute_file_hash, get_latest_staged_hash};
+use crate::{
+    head::Head,
+    helper::{compute_file_hash, get_latest_staged_hash},
+};
 
 pub fn status() -> io::Result<()> {
+    // "On branch master" or "HEAD detached at abc1234"
+    println!("{}", Head::read()?.describe());
+
     let index_file_path = ".rgit/index";
     let mut staged_files: Vec<(String, String)> = vec![]; // (file_path, file_hash)
 
@@ -1,71 +1,169 @@
 use std::{
-    fs::{self, File},
-    io::{self, Write},
-    path::Path,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-pub fn tag(tag_name: &str) -> io::Result<()> {
-    // TODO: Change heard code head_file value (currently master) to dynamic according to active branch
-    let head_content = fs::read_to_string(".rgit/refs/heads/master")?;
-    let tag_folder_path = Path::new(".rgit/refs/tags");
+use crate::{
+    config::get_config,
+    head::Head,
+    helper::hash_and_store_obj,
+    object::{read_object, Tag},
+    refs::{delete_ref, list_refs, read_ref, write_ref},
+    signing::{embed_signature, sign_payload, split_signature, verify_object},
+};
 
-    // If tags folder not created yet, then create it
-    if !tag_folder_path.exists() {
-        fs::create_dir_all(tag_folder_path)?;
-    }
+#[derive(Debug, Clone, Default)]
+pub struct TagOptions {
+    // Create an annotated tag object instead of a lightweight tag
+    pub annotate: bool,
+    // Tag message, implies an annotated tag
+    pub message: Option<String>,
+    // Sign the tag with an SSH key, implies an annotated tag
+    pub sign: bool,
+    // Key file used for signing instead of `user.signingKey`
+    pub signing_key: Option<String>,
+}
+
+pub fn tag(tag_name: &str, options: &TagOptions) -> io::Result<()> {
+    // Tag the commit HEAD points to, on the current branch or detached
+    let head_commit = Head::read()?.commit()?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "Failed to resolve 'HEAD' as a valid ref (nothing committed yet)",
+        )
+    })?;
+    let refname = format!("refs/tags/{}", tag_name);
 
-    // Create new tag file inside tags dir
-    let tag_file_path = tag_folder_path.join(tag_name);
-    // If tag file name already exist them skip the creation
-    if tag_file_path.exists() {
+    // If tag already exist them skip the creation
+    if read_ref(&refname)?.is_some() {
         println!("Tag '{tag_name}' already exist");
         return Ok(());
     }
 
-    let mut tag_file = File::create(tag_file_path)?;
+    // Annotated tags point to a tag object that points to the commit
+    let mut tag_target = head_commit;
+    if options.annotate || options.sign || options.message.is_some() {
+        let message = options.message.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A message is required for annotated tags (use -m)",
+            )
+        })?;
+        tag_target = create_tag_object(tag_name, &tag_target, message, options)?;
+    }
 
     // Store latest hashed commit blob to new tag file
-    tag_file.write_all(head_content.as_bytes())?;
+    write_ref(
+        &refname,
+        &tag_target,
+        None,
+        false,
+        &format!("tag: tagging {}", tag_name),
+    )?;
 
     println!("Tag '{}' created", tag_name);
     Ok(())
 }
 
-pub fn list_tags() -> io::Result<()> {
-    let tag_dir_path = Path::new(".rgit/refs/tags");
+// Write the annotated tag object and return its hash
+fn create_tag_object(
+    tag_name: &str,
+    commit_hash: &str,
+    message: &str,
+    options: &TagOptions,
+) -> io::Result<String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?
+        .as_secs();
+    let tagger_name = get_config("[user]", "name")?.unwrap_or("default".to_string());
+    let tagger_email = get_config("[user]", "email")?.unwrap_or("default@email.com".to_string());
 
-    if tag_dir_path.exists() {
-        for entry in fs::read_dir(tag_dir_path)? {
-            let entry = entry?;
-            let tag_name = entry.file_name().into_string().unwrap();
+    let mut tag_contents = format!(
+        "Object: {}\nType: commit\nTag: {}\nTagger: {} <{}> {} +0000\nMessage: {}",
+        commit_hash, tag_name, tagger_name, tagger_email, now, message
+    );
 
-            println!("{}", tag_name);
-        }
-    } else {
+    if options.sign {
+        let signature = sign_payload(&tag_contents, options.signing_key.as_deref())?;
+        tag_contents = embed_signature(&tag_contents, &signature);
+    }
+
+    hash_and_store_obj("tag", &tag_contents)
+}
+
+pub fn list_tags() -> io::Result<()> {
+    let tags = list_refs("refs/tags/")?;
+
+    if tags.is_empty() {
         eprintln!("No tags found");
     }
+    for (refname, _) in tags {
+        println!("{}", &refname["refs/tags/".len()..]);
+    }
 
     Ok(())
 }
 
 pub fn delete_tag(tag_name: &str) -> io::Result<()> {
-    // Check heads dir exist (to store branches)
-    let tag_folder_path = Path::new(".rgit/refs/tags");
-    if !tag_folder_path.exists() {
-        println!("No tag available to delete");
-        return Ok(());
-    }
-
-    let delete_tag_file = tag_folder_path.join(tag_name);
+    let refname = format!("refs/tags/{}", tag_name);
 
-    if !delete_tag_file.exists() {
+    if read_ref(&refname)?.is_none() {
         println!("Tag '{tag_name}' not found");
         return Ok(());
     } else {
-        // Delete branch file
-        fs::remove_file(delete_tag_file)?;
+        // Delete tag file
+        delete_ref(&refname, None, false)?;
         println!("Tag '{tag_name}' successfully deleted");
     }
 
     Ok(())
 }
+
+// Check the SSH signature of annotated tags
+pub fn verify_tag(names: &[String], verbose: bool) -> io::Result<()> {
+    let mut all_trusted = true;
+
+    for name in names {
+        let tag_hash = read_ref(&format!("refs/tags/{}", name))?.unwrap_or(name.to_string());
+
+        let (kind, contents) = read_object(&tag_hash)?;
+        if kind != "tag" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: cannot verify a non-tag object of type {}", name, kind),
+            ));
+        }
+        let contents = String::from_utf8_lossy(&contents).to_string();
+
+        if verbose {
+            let tag = Tag::parse(&split_signature(&contents).0)?;
+            println!("object {}", tag.object);
+            println!("type {}", tag.kind);
+            println!("tag {}", tag.name);
+            if let Some(tagger) = tag.tagger {
+                println!("tagger {}", tagger);
+            }
+            println!("\n{}", tag.message.trim_end());
+        }
+
+        match verify_object(&contents)? {
+            Some(check) => {
+                println!("{}", check.describe());
+                all_trusted &= check.is_trusted();
+            }
+            None => {
+                println!("Tag '{}' does not have a signature", name);
+                all_trusted = false;
+            }
+        }
+    }
+
+    if !all_trusted {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "signature verification failed",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,581 @@
+// Commit message trailers (e.g. "Signed-off-by: Name <email>") and interpret-trailers command
+
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use crate::config::list_config;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+    pub key: String,
+    // Multi-line values keep their folded form ("first\n second")
+    pub value: String,
+}
+
+impl Trailer {
+    // Value with continuation lines joined by a single space
+    pub fn unfolded_value(&self) -> String {
+        self.value.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn to_line(&self, unfold: bool) -> String {
+        if unfold {
+            format!("{}: {}", self.key, self.unfolded_value())
+        } else {
+            format!("{}: {}", self.key, self.value)
+        }
+    }
+}
+
+// Parse a single "Token: value" line, the token may only contain letters, digits and '-'
+fn parse_trailer_line(line: &str) -> Option<Trailer> {
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim_end();
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some(Trailer {
+        key: key.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+// Split a message into the text before the trailer block and the parsed trailers
+// The trailer block is the last paragraph of the message, as long as it is not the subject
+// paragraph and every line is either a trailer or an indented continuation line
+pub fn split_trailers(message: &str) -> (String, Vec<Trailer>) {
+    let trimmed = message.trim_end();
+    let lines: Vec<&str> = trimmed.lines().collect();
+
+    // Find where the last paragraph starts
+    let Some(blank_pos) = lines.iter().rposition(|l| l.trim().is_empty()) else {
+        return (message.to_string(), vec![]);
+    };
+    // The subject paragraph can never be a trailer block
+    if lines[..blank_pos].iter().all(|l| l.trim().is_empty()) {
+        return (message.to_string(), vec![]);
+    }
+
+    let mut trailers: Vec<Trailer> = vec![];
+    for line in &lines[blank_pos + 1..] {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            match trailers.last_mut() {
+                Some(last) => {
+                    last.value.push('\n');
+                    last.value.push_str(line);
+                }
+                None => return (message.to_string(), vec![]),
+            }
+        } else if let Some(trailer) = parse_trailer_line(line) {
+            trailers.push(trailer);
+        } else {
+            return (message.to_string(), vec![]);
+        }
+    }
+
+    let body = lines[..blank_pos].join("\n");
+    (format!("{}\n", body.trim_end()), trailers)
+}
+
+// Trailers of a commit message
+pub fn parse_trailers(message: &str) -> Vec<Trailer> {
+    split_trailers(message).1
+}
+
+// Rebuild the message from its body and trailers
+fn join_trailers(body: &str, trailers: &[Trailer]) -> String {
+    let body = body.trim_end();
+    let block = trailers
+        .iter()
+        .map(|t| t.to_line(false))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match (body.is_empty(), block.is_empty()) {
+        (_, true) => format!("{}\n", body),
+        (true, false) => format!("\n{}\n", block),
+        (false, false) => format!("{}\n\n{}\n", body, block),
+    }
+}
+
+// Where a new trailer is placed in the trailer block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailerWhere {
+    End,
+    Start,
+    After,
+    Before,
+}
+
+// What to do when a trailer with the same key already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailerIfExists {
+    AddIfDifferentNeighbor,
+    AddIfDifferent,
+    Add,
+    Replace,
+    DoNothing,
+}
+
+// What to do when no trailer with the same key exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailerIfMissing {
+    Add,
+    DoNothing,
+}
+
+impl TrailerWhere {
+    pub fn parse(value: &str) -> io::Result<TrailerWhere> {
+        match value.to_ascii_lowercase().as_str() {
+            "end" => Ok(TrailerWhere::End),
+            "start" => Ok(TrailerWhere::Start),
+            "after" => Ok(TrailerWhere::After),
+            "before" => Ok(TrailerWhere::Before),
+            _ => Err(invalid_setting("where", value)),
+        }
+    }
+}
+
+impl TrailerIfExists {
+    pub fn parse(value: &str) -> io::Result<TrailerIfExists> {
+        match value.to_ascii_lowercase().as_str() {
+            "addifdifferentneighbor" => Ok(TrailerIfExists::AddIfDifferentNeighbor),
+            "addifdifferent" => Ok(TrailerIfExists::AddIfDifferent),
+            "add" => Ok(TrailerIfExists::Add),
+            "replace" => Ok(TrailerIfExists::Replace),
+            "donothing" => Ok(TrailerIfExists::DoNothing),
+            _ => Err(invalid_setting("ifExists", value)),
+        }
+    }
+}
+
+impl TrailerIfMissing {
+    pub fn parse(value: &str) -> io::Result<TrailerIfMissing> {
+        match value.to_ascii_lowercase().as_str() {
+            "add" => Ok(TrailerIfMissing::Add),
+            "donothing" => Ok(TrailerIfMissing::DoNothing),
+            _ => Err(invalid_setting("ifMissing", value)),
+        }
+    }
+}
+
+fn invalid_setting(name: &str, value: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown trailer {} value '{}'", name, value),
+    )
+}
+
+// Trailer settings coming from the command line, they take precedence over configuration
+#[derive(Debug, Clone, Default)]
+pub struct TrailerOptions {
+    pub where_: Option<TrailerWhere>,
+    pub if_exists: Option<TrailerIfExists>,
+    pub if_missing: Option<TrailerIfMissing>,
+}
+
+// `trailer.<token>.*` configuration rule
+#[derive(Debug, Clone, Default)]
+struct TrailerRule {
+    token: String,
+    key: Option<String>,
+    where_: Option<TrailerWhere>,
+    if_exists: Option<TrailerIfExists>,
+    if_missing: Option<TrailerIfMissing>,
+}
+
+impl TrailerRule {
+    // A token matches a rule by its name or by a prefix of its configured key
+    fn matches(&self, token: &str) -> bool {
+        let token = token.to_ascii_lowercase();
+        self.token.to_ascii_lowercase() == token
+            || self
+                .key
+                .as_ref()
+                .is_some_and(|k| k.to_ascii_lowercase().starts_with(&token))
+    }
+}
+
+// Load global (`trailer.where`, ...) and per token (`trailer.<token>.*`) settings
+fn load_trailer_rules() -> io::Result<(TrailerRule, Vec<TrailerRule>)> {
+    let mut defaults = TrailerRule::default();
+    let mut rules: Vec<TrailerRule> = vec![];
+
+    for (name, value) in list_config()? {
+        let Some(rest) = name.strip_prefix("trailer.") else {
+            continue;
+        };
+
+        let (rule, setting) = match rest.rsplit_once('.') {
+            Some((token, setting)) => {
+                let pos = match rules.iter().position(|r| r.token == token) {
+                    Some(pos) => pos,
+                    None => {
+                        rules.push(TrailerRule {
+                            token: token.to_string(),
+                            ..Default::default()
+                        });
+                        rules.len() - 1
+                    }
+                };
+                (&mut rules[pos], setting)
+            }
+            None => (&mut defaults, rest),
+        };
+
+        match setting.to_ascii_lowercase().as_str() {
+            "key" => rule.key = Some(value),
+            "where" => rule.where_ = Some(TrailerWhere::parse(&value)?),
+            "ifexists" => rule.if_exists = Some(TrailerIfExists::parse(&value)?),
+            "ifmissing" => rule.if_missing = Some(TrailerIfMissing::parse(&value)?),
+            _ => {}
+        }
+    }
+
+    Ok((defaults, rules))
+}
+
+// Parse a "--trailer" argument ("token=value" or "token: value")
+pub fn parse_trailer_arg(arg: &str) -> io::Result<(String, String)> {
+    let sep = arg.find(['=', ':']).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid trailer '{}', expected <token>=<value>", arg),
+        )
+    })?;
+
+    let token = arg[..sep].trim();
+    if token.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Empty trailer token in '{}'", arg),
+        ));
+    }
+    Ok((token.to_string(), arg[sep + 1..].trim().to_string()))
+}
+
+// Add trailers to a message following the command line options and the trailer configuration
+pub fn add_trailers(
+    message: &str,
+    new_trailers: &[(String, String)],
+    options: &TrailerOptions,
+) -> io::Result<String> {
+    if new_trailers.is_empty() {
+        return Ok(message.to_string());
+    }
+
+    let (defaults, rules) = load_trailer_rules()?;
+    let (body, mut trailers) = split_trailers(message);
+
+    for (token, value) in new_trailers {
+        let rule = rules.iter().find(|r| r.matches(token));
+        let key = rule
+            .and_then(|r| r.key.clone())
+            .unwrap_or_else(|| token.clone());
+        let where_ = options
+            .where_
+            .or(rule.and_then(|r| r.where_))
+            .or(defaults.where_)
+            .unwrap_or(TrailerWhere::End);
+        let if_exists = options
+            .if_exists
+            .or(rule.and_then(|r| r.if_exists))
+            .or(defaults.if_exists)
+            .unwrap_or(TrailerIfExists::AddIfDifferentNeighbor);
+        let if_missing = options
+            .if_missing
+            .or(rule.and_then(|r| r.if_missing))
+            .or(defaults.if_missing)
+            .unwrap_or(TrailerIfMissing::Add);
+
+        let new_trailer = Trailer {
+            key,
+            value: value.clone(),
+        };
+        apply_trailer(&mut trailers, new_trailer, where_, if_exists, if_missing);
+    }
+
+    Ok(join_trailers(&body, &trailers))
+}
+
+fn apply_trailer(
+    trailers: &mut Vec<Trailer>,
+    new_trailer: Trailer,
+    where_: TrailerWhere,
+    if_exists: TrailerIfExists,
+    if_missing: TrailerIfMissing,
+) {
+    let same_key = |t: &Trailer| t.key.eq_ignore_ascii_case(&new_trailer.key);
+    let first_same = trailers.iter().position(same_key);
+    let last_same = trailers.iter().rposition(same_key);
+
+    if first_same.is_none() {
+        if if_missing == TrailerIfMissing::Add {
+            match where_ {
+                TrailerWhere::End | TrailerWhere::After => trailers.push(new_trailer),
+                TrailerWhere::Start | TrailerWhere::Before => trailers.insert(0, new_trailer),
+            }
+        }
+        return;
+    }
+
+    // Position the trailer would be inserted at and the existing trailer next to it
+    let (mut insert_at, neighbor) = match where_ {
+        TrailerWhere::End => (trailers.len(), trailers.len().checked_sub(1)),
+        TrailerWhere::Start => (0, Some(0)),
+        TrailerWhere::After => (last_same.unwrap() + 1, last_same),
+        TrailerWhere::Before => (first_same.unwrap(), first_same),
+    };
+
+    let is_same = |t: &Trailer| same_key(t) && t.value == new_trailer.value;
+    match if_exists {
+        TrailerIfExists::DoNothing => return,
+        TrailerIfExists::AddIfDifferent if trailers.iter().any(is_same) => return,
+        TrailerIfExists::AddIfDifferentNeighbor
+            if neighbor.is_some_and(|n| is_same(&trailers[n])) =>
+        {
+            return
+        }
+        TrailerIfExists::Replace => {
+            let existing = match where_ {
+                TrailerWhere::End | TrailerWhere::After => last_same.unwrap(),
+                TrailerWhere::Start | TrailerWhere::Before => first_same.unwrap(),
+            };
+            trailers.remove(existing);
+            if existing < insert_at {
+                insert_at -= 1;
+            }
+        }
+        _ => {}
+    }
+
+    trailers.insert(insert_at.min(trailers.len()), new_trailer);
+}
+
+// Format trailers of a message for `%(trailers[:options])` placeholders
+// Supported options: only, unfold, valueonly, key=<key>, separator=<sep>
+pub fn format_trailers(trailers: &[Trailer], options: &str) -> String {
+    let mut keys: Vec<String> = vec![];
+    let mut unfold = false;
+    let mut value_only = false;
+    let mut separator: Option<String> = None;
+
+    for option in options.split(',').filter(|o| !o.is_empty()) {
+        match option.split_once('=') {
+            Some(("key", key)) => {
+                keys.push(key.trim_end_matches(':').to_string());
+                unfold = true;
+            }
+            Some(("separator", sep)) => separator = Some(sep.replace("%n", "\n")),
+            Some(("unfold", v)) => unfold = matches!(v, "true" | "yes" | "on"),
+            Some(("valueonly", v)) => value_only = matches!(v, "true" | "yes" | "on"),
+            _ => match option {
+                "unfold" => unfold = true,
+                "valueonly" => value_only = true,
+                _ => {} // "only" is the default behavior since non-trailer lines are never kept
+            },
+        }
+    }
+
+    let lines: Vec<String> = trailers
+        .iter()
+        .filter(|t| keys.is_empty() || keys.iter().any(|k| k.eq_ignore_ascii_case(&t.key)))
+        .map(|t| {
+            let line = t.to_line(unfold);
+            if value_only {
+                line[t.key.len() + 2..].to_string()
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    match separator {
+        Some(sep) => lines.join(&sep),
+        None => lines.iter().map(|l| format!("{}\n", l)).collect(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InterpretTrailersOptions {
+    pub trailers: Vec<String>,
+    pub placement: TrailerOptions,
+    pub in_place: bool,
+    pub trim_empty: bool,
+    pub only_trailers: bool,
+    pub only_input: bool,
+    pub unfold: bool,
+}
+
+// Output of interpret-trailers for one message
+fn interpret_message(
+    input: &str,
+    new_trailers: &[(String, String)],
+    options: &InterpretTrailersOptions,
+) -> io::Result<String> {
+    let message = add_trailers(input, new_trailers, &options.placement)?;
+    let (body, mut trailers) = split_trailers(&message);
+
+    if options.trim_empty {
+        trailers.retain(|t| !t.value.trim().is_empty());
+    }
+    if options.unfold {
+        for trailer in trailers.iter_mut() {
+            trailer.value = trailer.unfolded_value();
+        }
+    }
+
+    if options.only_trailers {
+        Ok(trailers
+            .iter()
+            .map(|t| format!("{}\n", t.to_line(false)))
+            .collect())
+    } else if trailers.is_empty() {
+        Ok(body)
+    } else {
+        Ok(join_trailers(&body, &trailers))
+    }
+}
+
+// Add or parse trailers in commit messages read from files or stdin
+pub fn interpret_trailers(files: &[String], options: &InterpretTrailersOptions) -> io::Result<()> {
+    let new_trailers = if options.only_input {
+        vec![]
+    } else {
+        options
+            .trailers
+            .iter()
+            .map(|t| parse_trailer_arg(t))
+            .collect::<io::Result<Vec<_>>>()?
+    };
+
+    if files.is_empty() {
+        if options.in_place {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--in-place requires at least one file",
+            ));
+        }
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        print!("{}", interpret_message(&input, &new_trailers, options)?);
+    } else {
+        for file in files {
+            let input = fs::read_to_string(file)?;
+            let output = interpret_message(&input, &new_trailers, options)?;
+            if options.in_place {
+                fs::write(file, output)?;
+            } else {
+                print!("{}", output);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn split_trailers_of_last_paragraph() {
+        let (body, trailers) =
+            split_trailers("Subject\n\nBody\n\nSigned-off-by: A <a@x>\nFixes: #12\n");
+        assert_eq!(body, "Subject\n\nBody\n");
+        assert_eq!(
+            trailers,
+            vec![trailer("Signed-off-by", "A <a@x>"), trailer("Fixes", "#12")]
+        );
+    }
+
+    #[test]
+    fn split_trailers_keeps_continuation_lines() {
+        let (_, trailers) = split_trailers("Subject\n\nNote: first\n  second\n");
+        assert_eq!(trailers, vec![trailer("Note", "first\n  second")]);
+        assert_eq!(trailers[0].unfolded_value(), "first second");
+    }
+
+    #[test]
+    fn split_trailers_ignores_subject_and_prose() {
+        let message = "Fixes: not a trailer\n";
+        assert_eq!(split_trailers(message), (message.to_string(), vec![]));
+
+        let message = "Subject\n\nSigned-off-by: A <a@x>\nnot a trailer\n";
+        assert_eq!(split_trailers(message), (message.to_string(), vec![]));
+
+        let message = "Subject\n\n  continuation without a trailer\n";
+        assert_eq!(split_trailers(message), (message.to_string(), vec![]));
+    }
+
+    #[test]
+    fn interpret_adds_trailers() {
+        let options = InterpretTrailersOptions::default();
+        let new = vec![("Acked-by".to_string(), "B <b@x>".to_string())];
+        assert_eq!(
+            interpret_message("Subject\n", &new, &options).unwrap(),
+            "Subject\n\nAcked-by: B <b@x>\n"
+        );
+        assert_eq!(
+            interpret_message("Subject\n\nAcked-by: A <a@x>\n", &new, &options).unwrap(),
+            "Subject\n\nAcked-by: A <a@x>\nAcked-by: B <b@x>\n"
+        );
+        // The same trailer next to the new one is not repeated
+        assert_eq!(
+            interpret_message("Subject\n\nAcked-by: B <b@x>\n", &new, &options).unwrap(),
+            "Subject\n\nAcked-by: B <b@x>\n"
+        );
+    }
+
+    #[test]
+    fn interpret_placement_options() {
+        let new = vec![("Acked-by".to_string(), "C".to_string())];
+        let message = "Subject\n\nAcked-by: A\nFixes: #1\n";
+
+        let mut options = InterpretTrailersOptions::default();
+        options.placement.where_ = Some(TrailerWhere::After);
+        assert_eq!(
+            interpret_message(message, &new, &options).unwrap(),
+            "Subject\n\nAcked-by: A\nAcked-by: C\nFixes: #1\n"
+        );
+
+        options.placement.if_exists = Some(TrailerIfExists::Replace);
+        assert_eq!(
+            interpret_message(message, &new, &options).unwrap(),
+            "Subject\n\nAcked-by: C\nFixes: #1\n"
+        );
+
+        let options = InterpretTrailersOptions {
+            placement: TrailerOptions {
+                if_missing: Some(TrailerIfMissing::DoNothing),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let new = vec![("Reviewed-by".to_string(), "D".to_string())];
+        assert_eq!(interpret_message(message, &new, &options).unwrap(), message);
+    }
+
+    #[test]
+    fn interpret_only_trailers_unfold_and_trim_empty() {
+        let options = InterpretTrailersOptions {
+            only_trailers: true,
+            unfold: true,
+            trim_empty: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            interpret_message("Subject\n\nNote: a\n b\nEmpty:\n", &[], &options).unwrap(),
+            "Note: a b\n"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Updates of the working tree and the index from trees and index entries
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{helper::compute_file_hash, lockfile::LockFile, object::read_object};
+
+// Staged files of the index, the latest entry of a path wins
+pub fn read_index() -> io::Result<BTreeMap<String, String>> {
+    let mut entries = BTreeMap::new();
+    let index_file = match fs::File::open(".rgit/index") {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+
+    for line in BufReader::new(index_file).lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() == 2 {
+            entries.insert(parts[1].to_string(), parts[0].to_string());
+        }
+    }
+    Ok(entries)
+}
+
+// Take the index lock before reading the index that is going to be rewritten
+pub fn lock_index() -> io::Result<LockFile> {
+    LockFile::acquire(".rgit/index")
+}
+
+// Rewrite the index with one "<hash> <path>" line per staged file, releasing its lock
+pub fn write_index(mut lock: LockFile, entries: &BTreeMap<String, String>) -> io::Result<()> {
+    let contents: String = entries
+        .iter()
+        .map(|(path, hash)| format!("{} {}\n", hash, path))
+        .collect();
+    lock.write_all(contents.as_bytes())?;
+    lock.commit()
+}
+
+// Hash of the working tree file, None when it does not exist
+pub fn worktree_hash(path: &str) -> io::Result<Option<String>> {
+    match compute_file_hash(Path::new(path)) {
+        Ok(hash) => Ok(Some(hash)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Write the contents of a blob to a working tree file, creating its directories
+pub fn write_blob_file(path: &str, hash: &str) -> io::Result<()> {
+    let (_, contents) = read_object(hash)?;
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(path, contents)
+}
+
+// Remove a working tree file and the directories it leaves empty
+pub fn remove_worktree_file(path: &str) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let mut dir = Path::new(path).parent();
+    while let Some(parent) = dir {
+        if parent.as_os_str().is_empty() || fs::remove_dir(parent).is_err() {
+            break;
+        }
+        dir = parent.parent();
+    }
+    Ok(())
+}
+
+// Move the working tree and the index from the files of one tree to another.
+// Files that differ between the trees must not have local changes (unless forced),
+// other files keep their staged and unstaged changes.
+pub fn switch_tree(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    force: bool,
+) -> io::Result<()> {
+    let lock = lock_index()?;
+    let mut index = read_index()?;
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    let mut updates = vec![];
+    let mut changed = vec![];
+    let mut untracked = vec![];
+    for path in paths {
+        let (old_hash, new_hash) = (old.get(path), new.get(path));
+        if old_hash == new_hash && !force {
+            continue;
+        }
+        let worktree = worktree_hash(path)?;
+
+        if !force && old_hash.is_none() && !index.contains_key(path) {
+            // An untracked file only blocks the switch when it would be replaced
+            if worktree.is_some() && worktree.as_ref() != new_hash {
+                untracked.push(path.clone());
+            }
+        } else if !force {
+            // Clean when the index and the file still match one of the two trees
+            let index_hash = index.get(path);
+            let matches_old = index_hash == old_hash && worktree.as_ref() == old_hash;
+            let matches_new = index_hash == new_hash && worktree.as_ref() == new_hash;
+            if !matches_old && !matches_new {
+                changed.push(path.clone());
+            }
+        }
+        updates.push((path.clone(), new_hash.cloned()));
+    }
+
+    if !changed.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Your local changes to the following files would be overwritten by checkout:\n\t{}\nPlease commit your changes before you switch branches.",
+                changed.join("\n\t")
+            ),
+        ));
+    }
+    if !untracked.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "The following untracked working tree files would be overwritten by checkout:\n\t{}\nPlease move or remove them before you switch branches.",
+                untracked.join("\n\t")
+            ),
+        ));
+    }
+
+    for (path, hash) in updates {
+        match hash {
+            Some(hash) => {
+                write_blob_file(&path, &hash)?;
+                index.insert(path, hash);
+            }
+            None => {
+                remove_worktree_file(&path)?;
+                index.remove(&path);
+            }
+        }
+    }
+    write_index(lock, &index)
+}